@@ -115,13 +115,16 @@ pub async fn git_init(project_path: String) -> Result<bool, String> {
     runner::run_git_capture(&project_dir, &["init"]).await?;
 
     // Write .gitignore
+    // Patterns match at any depth, since the terraform root is configurable
     let gitignore_content = r#"# Terraform working files
-terraform/.terraform/
-terraform/*.tfstate
-terraform/*.tfstate.backup
-terraform/.terraform.lock.hcl
-terraform/terraform.tfvars
-terraform/tfplan
+**/.terraform/
+**/*.tfstate
+**/*.tfstate.backup
+**/.terraform.lock.hcl
+**/terraform.tfvars
+**/tfplan
+.terrastudio/plans/
+.terrastudio/runs/
 
 # OS files
 .DS_Store
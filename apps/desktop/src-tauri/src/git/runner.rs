@@ -1,9 +1,11 @@
 use serde::Serialize;
 use std::path::Path;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 
+use crate::process::{ProcessKind, ProcessRegistry};
+
 #[derive(Clone, Serialize)]
 pub struct GitOutput {
     pub stream: String,
@@ -94,6 +96,9 @@ pub async fn run_git(
         )
     })?;
 
+    let registry = app.state::<ProcessRegistry>();
+    let run = registry.register(window_label, ProcessKind::Git, args.first().unwrap_or(&""));
+
     let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
     let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
 
@@ -132,8 +137,8 @@ pub async fn run_git(
         }
     });
 
-    let status = child
-        .wait()
+    let (status, cancelled) = run
+        .wait(&mut child)
         .await
         .map_err(|e| format!("Failed to wait for git: {}", e))?;
 
@@ -141,7 +146,7 @@ pub async fn run_git(
     let _ = stderr_task.await;
 
     let code = status.code().unwrap_or(-1);
-    let success = status.success();
+    let success = !cancelled && status.success();
 
     let exit = GitExit { code, success };
     let _ = app.emit_to(window_label, "git:exit", exit.clone());
@@ -8,6 +8,7 @@ use tauri_plugin_log::{Target, TargetKind, RotationStrategy, TimezoneStrategy};
 
 mod azure;
 mod git;
+mod process;
 mod project;
 mod security;
 mod terraform;
@@ -102,6 +103,16 @@ pub fn run() {
             // Forward menu events to all webview windows
             let _ = app.emit("menu://action", id);
         })
+        .on_window_event(|window, event| {
+            // Don't leave terraform/git children running for a window that no longer exists
+            if let tauri::WindowEvent::Destroyed = event {
+                let registry = window.state::<process::ProcessRegistry>();
+                let cancelled = registry.cancel_window(window.label(), None);
+                if cancelled > 0 {
+                    log::info!("Cancelled {} run(s) for closed window '{}'", cancelled, window.label());
+                }
+            }
+        })
         .plugin(
             tauri_plugin_log::Builder::new()
                 .targets(log_targets)
@@ -148,6 +159,9 @@ pub fn run() {
             let pending_path = extract_tstudio_path(&std::env::args().collect::<Vec<_>>());
             app.manage(PendingOpenPath(Mutex::new(pending_path)));
             app.manage(PendingWindowPaths(Mutex::new(HashMap::new())));
+            app.manage(process::ProcessRegistry::default());
+            app.manage(terraform::lock::ProjectLocks::default());
+            app.manage(terraform::destroy::DestroyTokens::default());
 
             // Set up native menu bar (primarily for macOS global menu)
             let menu = build_menu(app.handle())?;
@@ -157,6 +171,7 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             project::commands::create_project,
+            project::import::import_terraform_project,
             project::commands::save_diagram,
             project::commands::load_project,
             project::commands::save_project_config,
@@ -172,10 +187,41 @@ pub fn run() {
             terraform::commands::terraform_validate,
             terraform::commands::terraform_plan,
             terraform::commands::terraform_apply,
-            terraform::commands::terraform_destroy,
+            terraform::commands::terraform_destroy_plan,
+            terraform::commands::terraform_destroy_apply,
             terraform::commands::terraform_plan_with_out,
             terraform::commands::terraform_apply_plan,
+            terraform::commands::terraform_apply_archived_plan,
+            terraform::commands::terraform_policy_check,
+            terraform::commands::terraform_detect_drift,
+            terraform::commands::terraform_import_plan,
             terraform::commands::terraform_show,
+            terraform::commands::terraform_show_resources,
+            terraform::commands::terraform_state_list,
+            terraform::commands::terraform_state_show,
+            terraform::commands::terraform_state_mv,
+            terraform::commands::terraform_state_rm,
+            terraform::commands::terraform_state_to_diagram,
+            terraform::commands::terraform_cancel,
+            terraform::commands::terraform_running,
+            terraform::commands::terraform_lock_holder,
+            terraform::workspace::terraform_workspace_list,
+            terraform::workspace::terraform_workspace_current,
+            terraform::workspace::terraform_workspace_new,
+            terraform::workspace::terraform_workspace_select,
+            terraform::workspace::terraform_workspace_delete,
+            terraform::versions::terraform_versions_list,
+            terraform::versions::terraform_version_install_zip,
+            terraform::versions::terraform_version_install_mirror,
+            terraform::versions::terraform_version_remove,
+            terraform::versions::terraform_version_resolve,
+            terraform::history::terraform_runs_list,
+            terraform::history::terraform_run_read,
+            terraform::history::terraform_runs_prune,
+            terraform::archive::terraform_plans_list,
+            terraform::archive::terraform_plan_text,
+            terraform::archive::terraform_plan_diff,
+            terraform::archive::terraform_plan_remove,
             terraform::commands::read_terraform_file,
             terraform::commands::list_terraform_files,
             project::templates::get_user_templates_dir,
@@ -185,6 +231,7 @@ pub fn run() {
             project::templates::open_templates_folder,
             project::secrets::load_user_secrets,
             project::secrets::save_user_secrets,
+            project::secrets::save_backend_secrets,
             project::secrets::delete_user_secrets,
             project::secrets::generate_secrets_id,
             azure::commands::az_list_subscriptions,
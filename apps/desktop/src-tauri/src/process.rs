@@ -0,0 +1,170 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::process::ExitStatus;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::process::Child;
+use tokio::sync::Notify;
+
+/// How long a cancelled process gets to exit after SIGINT before it is killed.
+/// Terraform uses this window to finish in-flight operations and release the state lock.
+const CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessKind {
+    Terraform,
+    Git,
+}
+
+/// A child process currently running on behalf of a window.
+#[derive(Clone, Serialize)]
+pub struct RunningProcess {
+    pub run_id: String,
+    pub window_label: String,
+    pub kind: ProcessKind,
+    pub command: String,
+}
+
+struct RegistryEntry {
+    info: RunningProcess,
+    cancel: Arc<Notify>,
+}
+
+type RegistryMap = Arc<Mutex<HashMap<String, RegistryEntry>>>;
+
+/// Managed state tracking spawned terraform and git children by run ID and window label.
+#[derive(Default)]
+pub struct ProcessRegistry {
+    runs: RegistryMap,
+}
+
+impl ProcessRegistry {
+    /// Register a new run. The entry is removed when the returned guard is dropped.
+    pub fn register(&self, window_label: &str, kind: ProcessKind, command: &str) -> RunGuard {
+        let run_id = uuid::Uuid::new_v4().to_string();
+        let cancel = Arc::new(Notify::new());
+        if let Ok(mut runs) = self.runs.lock() {
+            runs.insert(
+                run_id.clone(),
+                RegistryEntry {
+                    info: RunningProcess {
+                        run_id: run_id.clone(),
+                        window_label: window_label.to_string(),
+                        kind,
+                        command: command.to_string(),
+                    },
+                    cancel: cancel.clone(),
+                },
+            );
+        }
+        RunGuard {
+            run_id,
+            cancel,
+            runs: self.runs.clone(),
+        }
+    }
+
+    /// Request cancellation of a single run. Returns false if the run is unknown
+    /// or belongs to a different window.
+    pub fn cancel(&self, window_label: &str, run_id: &str) -> bool {
+        let Ok(runs) = self.runs.lock() else {
+            return false;
+        };
+        match runs.get(run_id) {
+            Some(entry) if entry.info.window_label == window_label => {
+                entry.cancel.notify_one();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Request cancellation of every run of the given kind owned by a window.
+    /// Passing `None` cancels all kinds. Returns the number of runs signalled.
+    pub fn cancel_window(&self, window_label: &str, kind: Option<ProcessKind>) -> usize {
+        let Ok(runs) = self.runs.lock() else {
+            return 0;
+        };
+        let mut count = 0;
+        for entry in runs.values() {
+            if entry.info.window_label == window_label && kind.map_or(true, |k| entry.info.kind == k) {
+                entry.cancel.notify_one();
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// List the runs currently owned by a window.
+    pub fn list(&self, window_label: &str) -> Vec<RunningProcess> {
+        self.runs
+            .lock()
+            .map(|runs| {
+                runs.values()
+                    .filter(|e| e.info.window_label == window_label)
+                    .map(|e| e.info.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Registration handle for a single run. Deregisters the run on drop.
+pub struct RunGuard {
+    run_id: String,
+    cancel: Arc<Notify>,
+    runs: RegistryMap,
+}
+
+impl RunGuard {
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// Wait for the child to exit, honouring cancellation requests.
+    /// On cancel, SIGINT is sent first; if the process is still running after
+    /// the grace period it is killed. Returns the exit status and whether the
+    /// run was cancelled.
+    pub async fn wait(&self, child: &mut Child) -> std::io::Result<(ExitStatus, bool)> {
+        tokio::select! {
+            status = child.wait() => Ok((status?, false)),
+            _ = self.cancel.notified() => {
+                log::info!("Cancelling run {}", self.run_id);
+                if interrupt(child) {
+                    if let Ok(status) = tokio::time::timeout(CANCEL_GRACE_PERIOD, child.wait()).await {
+                        return Ok((status?, true));
+                    }
+                    log::warn!("Run {} did not exit after SIGINT, killing", self.run_id);
+                }
+                child.kill().await?;
+                Ok((child.wait().await?, true))
+            }
+        }
+    }
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        if let Ok(mut runs) = self.runs.lock() {
+            runs.remove(&self.run_id);
+        }
+    }
+}
+
+/// Send SIGINT so the process can shut down gracefully. Returns false if no
+/// signal could be delivered, in which case the caller should kill outright.
+#[cfg(unix)]
+fn interrupt(child: &Child) -> bool {
+    match child.id() {
+        // SAFETY: kill(2) has no memory-safety preconditions; the pid belongs to our child.
+        Some(pid) => unsafe { libc::kill(pid as libc::pid_t, libc::SIGINT) == 0 },
+        None => false,
+    }
+}
+
+/// Windows has no SIGINT equivalent for detached console processes.
+#[cfg(not(unix))]
+fn interrupt(_child: &Child) -> bool {
+    false
+}
@@ -57,18 +57,56 @@ fn find_project_file(project_dir: &Path) -> Result<PathBuf, String> {
     ))
 }
 
+/// Read the `projectConfig` object from a project directory's project file.
+pub async fn read_project_config(project_dir: &Path) -> Result<serde_json::Value, String> {
+    Ok(read_project_metadata(project_dir).await?.project_config)
+}
+
+/// Read the project's display name from its project file.
+pub async fn read_project_name(project_dir: &Path) -> Result<String, String> {
+    Ok(read_project_metadata(project_dir).await?.name)
+}
+
+async fn read_project_metadata(project_dir: &Path) -> Result<ProjectMetadata, String> {
+    let metadata_path = find_project_file(project_dir)?;
+    let metadata_json = tokio::fs::read_to_string(&metadata_path)
+        .await
+        .map_err(|e| format!("Failed to read project file: {}", e))?;
+    serde_json::from_str(&metadata_json).map_err(|e| format!("Failed to parse project file: {}", e))
+}
+
 /// Build the canonical project file path: `{project_dir}/{name}.tstudio`
 fn project_file_path(project_dir: &Path, name: &str) -> PathBuf {
     project_dir.join(format!("{}.{}", name, PROJECT_EXTENSION))
 }
 
-/// Create a new project directory with the standard structure.
-#[command]
-pub async fn create_project(
-    name: String,
-    parent_path: String,
-) -> Result<ProjectData, String> {
-    let project_dir = PathBuf::from(&parent_path).join(&name);
+/// Default `projectConfig` for a new project.
+pub fn default_project_config(name: &str) -> serde_json::Value {
+    serde_json::json!({
+        "providerConfigs": {
+            "azurerm": {
+                "features": {}
+            }
+        },
+        "resourceGroupName": format!("rg-{}", name),
+        "resourceGroupAsVariable": true,
+        "location": "eastus",
+        "locationAsVariable": true,
+        "commonTags": {
+            "managed_by": "terrastudio"
+        },
+        "variableValues": {}
+    })
+}
+
+/// Create the project directory structure and write `{name}.tstudio`.
+/// Fails if the directory already exists.
+pub async fn scaffold_project(
+    name: &str,
+    parent_path: &str,
+    project_config: serde_json::Value,
+) -> Result<(PathBuf, ProjectMetadata), String> {
+    let project_dir = PathBuf::from(parent_path).join(name);
 
     if project_dir.exists() {
         return Err(format!(
@@ -88,35 +126,32 @@ pub async fn create_project(
         .await
         .map_err(|e| format!("Failed to create terraform directory: {}", e))?;
 
-    // Create default project metadata
     let metadata = ProjectMetadata {
-        name: name.clone(),
+        name: name.to_string(),
         version: "0.1.0".to_string(),
-        project_config: serde_json::json!({
-            "providerConfigs": {
-                "azurerm": {
-                    "features": {}
-                }
-            },
-            "resourceGroupName": format!("rg-{}", name),
-            "resourceGroupAsVariable": true,
-            "location": "eastus",
-            "locationAsVariable": true,
-            "commonTags": {
-                "managed_by": "terrastudio"
-            },
-            "variableValues": {}
-        }),
+        project_config,
     };
 
     // Write {name}.tstudio
-    let metadata_path = project_file_path(&project_dir, &name);
+    let metadata_path = project_file_path(&project_dir, name);
     let metadata_json = serde_json::to_string_pretty(&metadata)
         .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
     tokio::fs::write(&metadata_path, &metadata_json)
         .await
         .map_err(|e| format!("Failed to write project file: {}", e))?;
 
+    Ok((project_dir, metadata))
+}
+
+/// Create a new project directory with the standard structure.
+#[command]
+pub async fn create_project(
+    name: String,
+    parent_path: String,
+) -> Result<ProjectData, String> {
+    let (project_dir, metadata) =
+        scaffold_project(&name, &parent_path, default_project_config(&name)).await?;
+
     let project_path_str = project_dir.to_string_lossy().to_string();
 
     // Track in recent projects
@@ -0,0 +1,411 @@
+use hcl::{Block, Body, Expression, ObjectKey, Structure};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+use tauri::command;
+
+use super::commands::{default_project_config, scaffold_project, ProjectData};
+use super::recent;
+use crate::terraform::diagram::{self, DiagramTypeHint};
+use crate::terraform::state::{StateModule, StateResource, StateValues, TerraformState};
+
+/// Meta-arguments that change a resource's addressing or lifecycle. Resources
+/// using them are kept as raw HCL so regeneration cannot silently drop them.
+const META_ARGUMENTS: &[&str] = &["count", "for_each", "provider", "lifecycle", "depends_on", "provisioner", "connection"];
+
+/// Summary of an `import_terraform_project` run.
+#[derive(Serialize)]
+pub struct ImportedProject {
+    pub project: ProjectData,
+    /// Resources that became diagram nodes, by address.
+    pub mapped: Vec<String>,
+    /// Blocks kept verbatim in `projectConfig.rawHcl`, e.g. `module "net"`.
+    pub raw: Vec<String>,
+    /// Literal attributes of mapped resources that the diagram does not model,
+    /// e.g. `azurerm_storage_account.main.min_tls_version`.
+    pub dropped_attributes: Vec<String>,
+    /// Local module directories copied into the project.
+    pub modules: Vec<String>,
+}
+
+/// A resource block whose type the diagram knows.
+struct ParsedResource {
+    resource_type: String,
+    name: String,
+    block: Block,
+}
+
+/// Create a new project from an existing directory of `.tf` files.
+/// Resources whose type appears in `types` become diagram nodes; everything
+/// else (unknown types, modules, locals, variables, outputs, data sources) is
+/// kept as raw HCL that the generator writes back to `imported.tf`.
+#[command]
+pub async fn import_terraform_project(
+    name: String,
+    parent_path: String,
+    source_dir: String,
+    types: HashMap<String, DiagramTypeHint>,
+) -> Result<ImportedProject, String> {
+    let source_dir = PathBuf::from(&source_dir);
+    let body = read_tf_files(&source_dir).await?;
+
+    let mut project_config = default_project_config(&name);
+    // Imported configuration keeps its own variable declarations and literals
+    project_config["resourceGroupAsVariable"] = Value::Bool(false);
+    project_config["locationAsVariable"] = Value::Bool(false);
+    let mut raw_blocks: Vec<String> = Vec::new();
+    let mut raw = Vec::new();
+    let mut resources: Vec<ParsedResource> = Vec::new();
+    let mut module_sources = Vec::new();
+
+    for structure in body.into_inner() {
+        let block = match structure {
+            Structure::Block(block) => block,
+            Structure::Attribute(attr) => {
+                return Err(format!("Unexpected top-level attribute '{}'", attr.key.as_str()));
+            }
+        };
+        let labels: Vec<&str> = block.labels.iter().map(|l| l.as_str()).collect();
+        match (block.identifier.as_str(), labels.as_slice()) {
+            ("resource", [resource_type, name])
+                if types.contains_key(*resource_type) && !uses_meta_arguments(&block) =>
+            {
+                resources.push(ParsedResource {
+                    resource_type: resource_type.to_string(),
+                    name: name.to_string(),
+                    block,
+                });
+            }
+            ("terraform", []) => apply_terraform_block(&block, &mut project_config),
+            ("provider", [provider]) if attr(&block.body, "alias").is_none() => {
+                project_config["providerConfigs"][*provider] = Value::Object(literal_body(&block.body));
+            }
+            (identifier, _) => {
+                if identifier == "module" {
+                    if let Some(Expression::String(source)) = attr(&block.body, "source") {
+                        module_sources.push(source.clone());
+                    }
+                }
+                let description = std::iter::once(identifier.to_string())
+                    .chain(labels.iter().map(|l| format!("\"{}\"", l)))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                raw.push(description);
+                raw_blocks.push(
+                    hcl::to_string(&Body(vec![Structure::Block(block)]))
+                        .map_err(|e| format!("Failed to serialize {}: {}", raw.last().unwrap(), e))?,
+                );
+            }
+        }
+    }
+    let addresses: Vec<String> = resources
+        .iter()
+        .map(|r| format!("{}.{}", r.resource_type, r.name))
+        .collect();
+    let mut dropped_attributes = Vec::new();
+    let state_resources = resources
+        .iter()
+        .map(|r| to_state_resource(r, &addresses, types.get(&r.resource_type), &mut dropped_attributes))
+        .collect();
+    let state = TerraformState {
+        values: Some(StateValues {
+            outputs: HashMap::new(),
+            root_module: StateModule {
+                address: None,
+                resources: state_resources,
+                child_modules: Vec::new(),
+            },
+        }),
+        ..TerraformState::default()
+    };
+    let import = diagram::state_to_diagram(&state, &types);
+    // Diagram names are normalized (e.g. lowercased); keep existing state attached
+    for (from, to) in &import.renamed {
+        raw.push(format!("moved {} -> {}", from, to));
+        raw_blocks.push(format!("moved {{\n  from = {}\n  to   = {}\n}}\n", from, to));
+    }
+    project_config["rawHcl"] = Value::from(raw_blocks);
+
+    let (project_dir, metadata) = scaffold_project(&name, &parent_path, project_config).await?;
+
+    let diagram_json = serde_json::to_string_pretty(&import.diagram)
+        .map_err(|e| format!("Failed to serialize diagram: {}", e))?;
+    tokio::fs::write(project_dir.join("diagrams").join("main.json"), &diagram_json)
+        .await
+        .map_err(|e| format!("Failed to write diagram: {}", e))?;
+
+    let modules = copy_local_modules(&source_dir, &project_dir.join("terraform"), &module_sources).await?;
+
+    let project_path_str = project_dir.to_string_lossy().to_string();
+    let _ = recent::add_recent(&name, &project_path_str);
+
+    Ok(ImportedProject {
+        project: ProjectData {
+            metadata,
+            diagram: Some(import.diagram),
+            cost: None,
+            path: project_path_str,
+        },
+        mapped: addresses,
+        raw,
+        dropped_attributes,
+        modules,
+    })
+}
+
+/// Parse every `*.tf` file directly inside `dir`, in file name order, into one body.
+async fn read_tf_files(dir: &Path) -> Result<Body, String> {
+    let mut entries = tokio::fs::read_dir(dir)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    let mut paths = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+    {
+        let path = entry.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "tf") {
+            paths.push(path);
+        }
+    }
+    if paths.is_empty() {
+        return Err(format!("No .tf files found in {}", dir.display()));
+    }
+    paths.sort();
+
+    let mut structures = Vec::new();
+    for path in paths {
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let body = hcl::parse(&content).map_err(|e| {
+            format!(
+                "Failed to parse {}: {}",
+                path.file_name().unwrap_or_default().to_string_lossy(),
+                e
+            )
+        })?;
+        structures.extend(body.into_inner());
+    }
+    Ok(Body(structures))
+}
+
+fn attr<'a>(body: &'a Body, key: &str) -> Option<&'a Expression> {
+    body.attributes().find(|a| a.key.as_str() == key).map(|a| &a.expr)
+}
+
+fn uses_meta_arguments(block: &Block) -> bool {
+    block.body.iter().any(|s| match s {
+        Structure::Attribute(a) => META_ARGUMENTS.contains(&a.key.as_str()),
+        Structure::Block(b) => META_ARGUMENTS.contains(&b.identifier.as_str()),
+    })
+}
+
+/// Pick the backend out of a `terraform {}` block; the rest is regenerated.
+/// Only string settings are kept, matching `projectConfig.backend.config`.
+fn apply_terraform_block(block: &Block, project_config: &mut Value) {
+    for backend in block.body.blocks().filter(|b| b.identifier.as_str() == "backend") {
+        let Some(label) = backend.labels.first() else { continue };
+        let config: Map<String, Value> = literal_body(&backend.body)
+            .into_iter()
+            .filter(|(_, v)| v.is_string())
+            .collect();
+        project_config["backend"] = serde_json::json!({ "type": label.as_str(), "config": config });
+    }
+}
+
+/// Convert an expression to JSON if it is a plain literal (no references,
+/// functions or templates).
+fn literal(expr: &Expression) -> Option<Value> {
+    match expr {
+        Expression::Null => Some(Value::Null),
+        Expression::Bool(b) => Some(Value::Bool(*b)),
+        Expression::Number(n) => serde_json::to_value(n).ok(),
+        Expression::String(s) => Some(Value::String(s.clone())),
+        Expression::Array(items) => items.iter().map(literal).collect::<Option<Vec<_>>>().map(Value::Array),
+        Expression::Object(object) => object
+            .iter()
+            .map(|(key, value)| {
+                let key = match key {
+                    ObjectKey::Identifier(ident) => ident.to_string(),
+                    ObjectKey::Expression(Expression::String(s)) => s.clone(),
+                    _ => return None,
+                };
+                literal(value).map(|v| (key, v))
+            })
+            .collect::<Option<Map<_, _>>>()
+            .map(Value::Object),
+        Expression::Parenthesis(inner) => literal(inner),
+        _ => None,
+    }
+}
+
+/// Literal attributes of a body, plus nested blocks as objects. Non-literal
+/// attributes are skipped.
+fn literal_body(body: &Body) -> Map<String, Value> {
+    let mut out = Map::new();
+    for structure in body.iter() {
+        match structure {
+            Structure::Attribute(a) => {
+                if let Some(value) = literal(&a.expr) {
+                    out.insert(a.key.to_string(), value);
+                }
+            }
+            Structure::Block(b) => {
+                out.insert(b.identifier.to_string(), Value::Object(literal_body(&b.body)));
+            }
+        }
+    }
+    out
+}
+
+/// Addresses of declared resources mentioned in an expression.
+fn referenced_addresses(expr: &Expression, addresses: &[String]) -> Vec<Value> {
+    let text = expr.to_string();
+    addresses
+        .iter()
+        .filter(|address| {
+            text.match_indices(address.as_str()).any(|(pos, _)| {
+                let before = text[..pos].chars().next_back();
+                let after = text[pos + address.len()..].chars().next();
+                let boundary = |c: Option<char>| c.map_or(true, |c| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'));
+                boundary(before) && before != Some('.') && boundary(after)
+            })
+        })
+        .map(|address| Value::String(address.clone()))
+        .collect()
+}
+
+/// Convert a body into state-style values. Literals are kept as-is; other
+/// expressions are replaced by the addresses they reference, which the diagram
+/// builder resolves into containment and references. Those entries are masked
+/// in the returned map so they are never copied into node properties.
+fn body_values(body: &Body, addresses: &[String]) -> (Map<String, Value>, Map<String, Value>) {
+    let mut values = Map::new();
+    let mut computed = Map::new();
+    for structure in body.iter() {
+        match structure {
+            Structure::Attribute(a) => match literal(&a.expr) {
+                Some(value) => {
+                    values.insert(a.key.to_string(), value);
+                }
+                None => {
+                    values.insert(a.key.to_string(), Value::Array(referenced_addresses(&a.expr, addresses)));
+                    computed.insert(a.key.to_string(), Value::Bool(true));
+                }
+            },
+            Structure::Block(b) => {
+                let (nested, nested_computed) = body_values(&b.body, addresses);
+                if !nested_computed.is_empty() {
+                    computed.insert(b.identifier.to_string(), Value::Bool(true));
+                }
+                // Repeated nested blocks (e.g. several `ip_configuration`) become a list
+                match values.get_mut(b.identifier.as_str()) {
+                    Some(Value::Array(items)) => items.push(Value::Object(nested)),
+                    _ => {
+                        values.insert(b.identifier.to_string(), Value::Array(vec![Value::Object(nested)]));
+                    }
+                }
+            }
+        }
+    }
+    (values, computed)
+}
+
+fn to_state_resource(
+    resource: &ParsedResource,
+    addresses: &[String],
+    hint: Option<&DiagramTypeHint>,
+    dropped: &mut Vec<String>,
+) -> StateResource {
+    let address = format!("{}.{}", resource.resource_type, resource.name);
+    let (mut values, computed) = body_values(&resource.block.body, addresses);
+
+    if let Some(hint) = hint {
+        for (key, _) in values.iter().filter(|(k, _)| !computed.contains_key(*k)) {
+            if !hint.properties.contains(key) {
+                dropped.push(format!("{}.{}", address, key));
+            }
+        }
+    }
+
+    // Stand-in ID so references by address resolve like Azure resource IDs do in state
+    values.insert("id".to_string(), Value::String(address.clone()));
+
+    StateResource {
+        address,
+        mode: "managed".to_string(),
+        resource_type: resource.resource_type.clone(),
+        name: resource.name.clone(),
+        index: None,
+        provider_name: String::new(),
+        schema_version: 0,
+        values: Value::Object(values),
+        sensitive_values: Value::Object(computed),
+        depends_on: Vec::new(),
+        tainted: false,
+        deposed_key: None,
+    }
+}
+
+/// Copy module directories referenced by local `source` paths (`./modules/net`)
+/// into the project's terraform directory at the same relative path.
+/// Sources outside the imported directory are left for the user to resolve.
+async fn copy_local_modules(source_dir: &Path, terraform_dir: &Path, sources: &[String]) -> Result<Vec<String>, String> {
+    let mut copied = BTreeSet::new();
+    for source in sources {
+        if !source.starts_with("./") {
+            if source.starts_with("../") {
+                log::warn!("Module source {} is outside the imported directory; not copied", source);
+            }
+            continue;
+        }
+        let relative = Path::new(source);
+        if relative.components().any(|c| matches!(c, Component::ParentDir)) {
+            log::warn!("Module source {} escapes the imported directory; not copied", source);
+            continue;
+        }
+        if copied.insert(source.clone()) {
+            copy_dir(&source_dir.join(relative), &terraform_dir.join(relative)).await?;
+        }
+    }
+    Ok(copied.into_iter().collect())
+}
+
+async fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    let mut pending = vec![(from.to_path_buf(), to.to_path_buf())];
+    while let Some((src, dst)) = pending.pop() {
+        tokio::fs::create_dir_all(&dst)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", dst.display(), e))?;
+        let mut entries = tokio::fs::read_dir(&src)
+            .await
+            .map_err(|e| format!("Failed to read module directory {}: {}", src.display(), e))?;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| format!("Failed to read module directory {}: {}", src.display(), e))?
+        {
+            let path = entry.path();
+            let file_name = entry.file_name();
+            // Skip provider caches and state; they are not part of the module source
+            if file_name == ".terraform" {
+                continue;
+            }
+            let file_type = entry
+                .file_type()
+                .await
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if file_type.is_dir() {
+                pending.push((path, dst.join(&file_name)));
+            } else if file_type.is_file() {
+                tokio::fs::copy(&path, dst.join(&file_name))
+                    .await
+                    .map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,4 +1,5 @@
 pub mod commands;
+pub mod import;
 pub mod recent;
 pub mod secrets;
 pub mod templates;
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 const APP_DIR_NAME: &str = "com.terrastudio.app";
 const SECRETS_DIR: &str = "secrets";
@@ -10,7 +10,7 @@ fn get_secrets_dir() -> Result<PathBuf, String> {
     Ok(data_dir.join(APP_DIR_NAME).join(SECRETS_DIR))
 }
 
-fn get_secrets_path(secrets_id: &str) -> Result<PathBuf, String> {
+fn sanitize_secrets_id(secrets_id: &str) -> Result<&str, String> {
     if secrets_id.is_empty()
         || !secrets_id
             .chars()
@@ -18,53 +18,95 @@ fn get_secrets_path(secrets_id: &str) -> Result<PathBuf, String> {
     {
         return Err(format!("Invalid secrets ID: {}", secrets_id));
     }
-    Ok(get_secrets_dir()?.join(format!("{}.json", secrets_id)))
+    Ok(secrets_id)
 }
 
-/// Load user secrets for a project by its secrets ID.
-#[tauri::command]
-pub async fn load_user_secrets(secrets_id: String) -> Result<HashMap<String, String>, String> {
-    let path = get_secrets_path(&secrets_id)?;
+fn get_secrets_path(secrets_id: &str) -> Result<PathBuf, String> {
+    Ok(get_secrets_dir()?.join(format!("{}.json", sanitize_secrets_id(secrets_id)?)))
+}
+
+/// Backend credentials live beside the variable secrets, in their own file, so
+/// saving the variables never touches them.
+fn get_backend_secrets_path(secrets_id: &str) -> Result<PathBuf, String> {
+    Ok(get_secrets_dir()?.join(format!("{}.backend.json", sanitize_secrets_id(secrets_id)?)))
+}
+
+async fn read_secrets_file(path: &Path) -> Result<HashMap<String, String>, String> {
     if !path.exists() {
         return Ok(HashMap::new());
     }
-    let content = tokio::fs::read_to_string(&path)
+    let content = tokio::fs::read_to_string(path)
         .await
         .map_err(|e| format!("Failed to read secrets: {}", e))?;
     serde_json::from_str(&content).map_err(|e| format!("Failed to parse secrets: {}", e))
 }
 
-/// Save user secrets for a project by its secrets ID.
-#[tauri::command]
-pub async fn save_user_secrets(
-    secrets_id: String,
-    secrets: HashMap<String, String>,
-) -> Result<(), String> {
-    let path = get_secrets_path(&secrets_id)?;
+async fn write_secrets_file(path: &Path, secrets: &HashMap<String, String>) -> Result<(), String> {
     if let Some(parent) = path.parent() {
         tokio::fs::create_dir_all(parent)
             .await
             .map_err(|e| format!("Failed to create secrets directory: {}", e))?;
     }
-    let json = serde_json::to_string_pretty(&secrets)
+    let json = serde_json::to_string_pretty(secrets)
         .map_err(|e| format!("Failed to serialize secrets: {}", e))?;
-    tokio::fs::write(&path, &json)
+    tokio::fs::write(path, &json)
         .await
         .map_err(|e| format!("Failed to write secrets: {}", e))
 }
 
-/// Delete user secrets for a project.
-#[tauri::command]
-pub async fn delete_user_secrets(secrets_id: String) -> Result<(), String> {
-    let path = get_secrets_path(&secrets_id)?;
+async fn remove_secrets_file(path: &Path) -> Result<(), String> {
     if path.exists() {
-        tokio::fs::remove_file(&path)
+        tokio::fs::remove_file(path)
             .await
             .map_err(|e| format!("Failed to delete secrets: {}", e))?;
     }
     Ok(())
 }
 
+/// Read the secrets stored for a secrets ID. Missing files yield an empty map.
+pub async fn read_secrets(secrets_id: &str) -> Result<HashMap<String, String>, String> {
+    read_secrets_file(&get_secrets_path(secrets_id)?).await
+}
+
+/// Read the backend credentials stored for a secrets ID, keyed by backend setting.
+/// Missing files yield an empty map.
+pub async fn read_backend_secrets(secrets_id: &str) -> Result<HashMap<String, String>, String> {
+    read_secrets_file(&get_backend_secrets_path(secrets_id)?).await
+}
+
+/// Load user secrets for a project by its secrets ID.
+#[tauri::command]
+pub async fn load_user_secrets(secrets_id: String) -> Result<HashMap<String, String>, String> {
+    read_secrets(&secrets_id).await
+}
+
+/// Save user secrets for a project by its secrets ID.
+#[tauri::command]
+pub async fn save_user_secrets(
+    secrets_id: String,
+    secrets: HashMap<String, String>,
+) -> Result<(), String> {
+    write_secrets_file(&get_secrets_path(&secrets_id)?, &secrets).await
+}
+
+/// Save the backend credentials (e.g. `access_key`, `password`) for a project by
+/// its secrets ID. They are passed to `terraform init` with the backend settings
+/// from the project file, which must not hold credentials itself.
+#[tauri::command]
+pub async fn save_backend_secrets(
+    secrets_id: String,
+    secrets: HashMap<String, String>,
+) -> Result<(), String> {
+    write_secrets_file(&get_backend_secrets_path(&secrets_id)?, &secrets).await
+}
+
+/// Delete user secrets for a project, backend credentials included.
+#[tauri::command]
+pub async fn delete_user_secrets(secrets_id: String) -> Result<(), String> {
+    remove_secrets_file(&get_secrets_path(&secrets_id)?).await?;
+    remove_secrets_file(&get_backend_secrets_path(&secrets_id)?).await
+}
+
 /// Generate a new UUID v4 secrets ID.
 #[tauri::command]
 pub fn generate_secrets_id() -> String {
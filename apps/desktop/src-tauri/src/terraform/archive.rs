@@ -0,0 +1,548 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::command;
+
+use super::generated;
+use super::integrity;
+use super::plan::{ResourceChange, TerraformPlan};
+use super::runner::{self, RunContext};
+
+/// Archived plans, one directory per plan ID, relative to the project dir.
+const ARCHIVE_DIR: &str = ".terrastudio/plans/archive";
+
+/// Archived plan files, `<plan ID>.tfplan`, relative to the app data dir. A saved
+/// plan holds every variable value in plaintext, so it is kept out of the project.
+const APP_DIR_NAME: &str = "com.terrastudio.app";
+const PLAN_STORE_DIR: &str = "plans";
+
+/// Files inside an archived plan's directory.
+const JSON_FILE: &str = "plan.json";
+const TEXT_FILE: &str = "plan.txt";
+const META_FILE: &str = "meta.json";
+const BINDING_FILE: &str = "binding.json";
+
+/// Counts of the actionable changes in a plan.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PlanCounts {
+    pub create: usize,
+    pub update: usize,
+    pub delete: usize,
+    pub replace: usize,
+    pub import: usize,
+}
+
+/// An archived plan as listed; stored as `meta.json` beside the plan.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ArchivedPlan {
+    /// UTC timestamp plus a random suffix, e.g. `20261017T142530Z-1a2b3c4d`.
+    pub id: String,
+    /// What produced the plan, e.g. `plan` or `import`.
+    pub command: String,
+    /// Workspace the plan was created in, resolved at archive time.
+    /// `None` only for plans archived before it was recorded.
+    pub workspace: Option<String>,
+    pub engine: String,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: u64,
+    pub counts: PlanCounts,
+    /// Paths of the renderings, for attaching to change tickets. Variables are
+    /// left out of both and sensitive values are masked.
+    pub json_path: String,
+    pub text_path: String,
+}
+
+/// Validate a plan ID before it is used as a directory name.
+fn sanitize_plan_id(id: &str) -> Result<&str, String> {
+    if id.is_empty() || id.len() > 64 || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Invalid plan ID: {}", id));
+    }
+    Ok(id)
+}
+
+fn plan_dir(project_dir: &Path, id: &str) -> Result<PathBuf, String> {
+    Ok(project_dir.join(ARCHIVE_DIR).join(sanitize_plan_id(id)?))
+}
+
+/// The archived plan file for `id`, for applying it.
+pub fn plan_path(id: &str) -> Result<PathBuf, String> {
+    let data_dir = dirs::data_dir().ok_or_else(|| "Could not determine app data directory".to_string())?;
+    Ok(data_dir
+        .join(APP_DIR_NAME)
+        .join(PLAN_STORE_DIR)
+        .join(format!("{}.tfplan", sanitize_plan_id(id)?)))
+}
+
+fn new_plan_id() -> String {
+    let now = time::OffsetDateTime::now_utc();
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z-{}",
+        now.year(),
+        now.month() as u8,
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second(),
+        &suffix[..8]
+    )
+}
+
+/// Replacement for sensitive values, as in terraform's own plan output.
+const SENSITIVE_VALUE: &str = "(sensitive value)";
+
+/// Replace every part of `value` that `sensitive` marks `true`.
+/// `sensitive` mirrors the shape of `value`, as in `after_sensitive`.
+fn mask(value: &mut serde_json::Value, sensitive: &serde_json::Value) {
+    use serde_json::Value;
+    match (value, sensitive) {
+        (value, Value::Bool(true)) => *value = Value::String(SENSITIVE_VALUE.to_string()),
+        (Value::Object(map), Value::Object(marks)) => {
+            for (key, mark) in marks {
+                if let Some(child) = map.get_mut(key) {
+                    mask(child, mark);
+                }
+            }
+        }
+        (Value::Array(items), Value::Array(marks)) => {
+            for (item, mark) in items.iter_mut().zip(marks) {
+                mask(item, mark);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Mask `change.before`/`change.after` by their `*_sensitive` markers.
+fn mask_change(change: &mut serde_json::Value) {
+    for (key, marks) in [("before", "before_sensitive"), ("after", "after_sensitive")] {
+        let Some(marks) = change.get(marks).cloned() else {
+            continue;
+        };
+        if let Some(value) = change.get_mut(key) {
+            mask(value, &marks);
+        }
+    }
+}
+
+/// Mask outputs and resource values of a `values` representation
+/// (`planned_values`, `prior_state.values`).
+fn mask_values(values: &mut serde_json::Value) {
+    if let Some(outputs) = values.get_mut("outputs").and_then(|o| o.as_object_mut()) {
+        for output in outputs.values_mut() {
+            if output.get("sensitive").and_then(|s| s.as_bool()) == Some(true) {
+                if let Some(value) = output.get_mut("value") {
+                    *value = serde_json::Value::String(SENSITIVE_VALUE.to_string());
+                }
+            }
+        }
+    }
+    let mut modules: Vec<&mut serde_json::Value> = values.get_mut("root_module").into_iter().collect();
+    while let Some(module) = modules.pop() {
+        let module = module.as_object_mut().into_iter().flat_map(|m| m.iter_mut());
+        for (key, entry) in module {
+            match key.as_str() {
+                "resources" => {
+                    for resource in entry.as_array_mut().into_iter().flatten() {
+                        if let Some(marks) = resource.get("sensitive_values").cloned() {
+                            if let Some(value) = resource.get_mut("values") {
+                                mask(value, &marks);
+                            }
+                        }
+                    }
+                }
+                "child_modules" => modules.extend(entry.as_array_mut().into_iter().flatten()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Drop defaults of sensitive variables from the `configuration` section.
+fn mask_configuration(configuration: &mut serde_json::Value) {
+    let mut modules: Vec<&mut serde_json::Value> = configuration.get_mut("root_module").into_iter().collect();
+    while let Some(module) = modules.pop() {
+        let module = module.as_object_mut().into_iter().flat_map(|m| m.iter_mut());
+        for (key, entry) in module {
+            match key.as_str() {
+                "variables" => {
+                    for variable in entry.as_object_mut().into_iter().flat_map(|v| v.values_mut()) {
+                        if variable.get("sensitive").and_then(|s| s.as_bool()) == Some(true) {
+                            if let Some(variable) = variable.as_object_mut() {
+                                variable.remove("default");
+                            }
+                        }
+                    }
+                }
+                "module_calls" => {
+                    for call in entry.as_object_mut().into_iter().flat_map(|c| c.values_mut()) {
+                        modules.extend(call.get_mut("module"));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Make `terraform show -json` output safe to share: drop the input variables,
+/// which terraform records in plaintext, and mask every sensitive value.
+fn redact_plan_json(json: &str) -> Result<String, String> {
+    let mut plan: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse terraform plan JSON: {}", e))?;
+    let Some(root) = plan.as_object_mut() else {
+        return Err("Failed to parse terraform plan JSON: not an object".to_string());
+    };
+    root.remove("variables");
+    for key in ["resource_changes", "resource_drift"] {
+        for rc in root.get_mut(key).and_then(|v| v.as_array_mut()).into_iter().flatten() {
+            if let Some(change) = rc.get_mut("change") {
+                mask_change(change);
+            }
+        }
+    }
+    for change in root
+        .get_mut("output_changes")
+        .and_then(|v| v.as_object_mut())
+        .into_iter()
+        .flat_map(|o| o.values_mut())
+    {
+        mask_change(change);
+    }
+    if let Some(values) = root.get_mut("planned_values") {
+        mask_values(values);
+    }
+    if let Some(values) = root.get_mut("prior_state").and_then(|s| s.get_mut("values")) {
+        mask_values(values);
+    }
+    if let Some(configuration) = root.get_mut("configuration") {
+        mask_configuration(configuration);
+    }
+    serde_json::to_string_pretty(&plan).map_err(|e| format!("Failed to serialize plan JSON: {}", e))
+}
+
+fn count_changes(plan: &TerraformPlan) -> PlanCounts {
+    let mut counts = PlanCounts::default();
+    for rc in plan.actionable_changes() {
+        if rc.change.importing.is_some() {
+            counts.import += 1;
+        }
+        let actions = &rc.change.actions;
+        if actions.iter().any(|a| a == "create") && actions.iter().any(|a| a == "delete") {
+            counts.replace += 1;
+        } else if actions.iter().any(|a| a == "create") {
+            counts.create += 1;
+        } else if actions.iter().any(|a| a == "update") {
+            counts.update += 1;
+        } else if actions.iter().any(|a| a == "delete") {
+            counts.delete += 1;
+        }
+    }
+    counts
+}
+
+/// Copy the saved plan `plan_file` (relative to `terraform_dir`) into the archive
+/// with its JSON and plain-text renderings and its integrity binding. The plan
+/// file itself goes to the app data dir; only the masked renderings stay in
+/// the project. Returns the new plan ID.
+pub async fn archive_plan(
+    project_dir: &Path,
+    terraform_dir: &Path,
+    ctx: &RunContext,
+    plan_file: &str,
+    command: &str,
+) -> Result<String, String> {
+    let source = terraform_dir.join(plan_file);
+    let source_str = source.to_string_lossy().to_string();
+    let json = runner::run_terraform_capture(terraform_dir, ctx, "show", &["-json", &source_str]).await?;
+    let text = runner::run_terraform_capture(terraform_dir, ctx, "show", &["-no-color", &source_str]).await?;
+    let plan: TerraformPlan =
+        serde_json::from_str(&json).map_err(|e| format!("Failed to parse terraform plan JSON: {}", e))?;
+    let json = redact_plan_json(&json)?;
+    // Pin the workspace so applying the plan later cannot target another one
+    let workspace = match &ctx.workspace {
+        Some(workspace) => workspace.clone(),
+        None => runner::run_terraform_capture(terraform_dir, ctx, "workspace", &["show"])
+            .await?
+            .trim()
+            .to_string(),
+    };
+
+    let id = new_plan_id();
+    let dir = plan_dir(project_dir, &id)?;
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let stored = plan_path(&id)?;
+    if let Some(parent) = stored.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    tokio::fs::copy(&source, &stored)
+        .await
+        .map_err(|e| format!("Failed to archive {}: {}", plan_file, e))?;
+    tokio::fs::copy(integrity::binding_path(project_dir, plan_file), dir.join(BINDING_FILE))
+        .await
+        .map_err(|e| format!("Failed to archive plan binding: {}", e))?;
+    tokio::fs::write(dir.join(JSON_FILE), json)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", JSON_FILE, e))?;
+    tokio::fs::write(dir.join(TEXT_FILE), text)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", TEXT_FILE, e))?;
+
+    let meta = ArchivedPlan {
+        id: id.clone(),
+        command: command.to_string(),
+        workspace: Some(workspace),
+        engine: ctx.engine.name().to_string(),
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        counts: count_changes(&plan),
+        json_path: dir.join(JSON_FILE).to_string_lossy().to_string(),
+        text_path: dir.join(TEXT_FILE).to_string_lossy().to_string(),
+    };
+    let meta_json =
+        serde_json::to_string_pretty(&meta).map_err(|e| format!("Failed to serialize plan metadata: {}", e))?;
+    // Written last: a directory without metadata is an incomplete archive and is not listed
+    generated::write_atomic(&dir.join(META_FILE), meta_json.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", META_FILE, e))?;
+    Ok(id)
+}
+
+/// Read an archived plan's metadata.
+pub async fn read_meta(project_dir: &Path, id: &str) -> Result<ArchivedPlan, String> {
+    let path = plan_dir(project_dir, id)?.join(META_FILE);
+    let json = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|_| format!("Archived plan {} not found", id))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse archived plan {}: {}", id, e))
+}
+
+/// Compare an archived plan against the configuration and git HEAD on disk.
+pub async fn check(project_dir: &Path, terraform_dir: &Path, id: &str) -> Result<Option<integrity::StalePlan>, String> {
+    let binding = plan_dir(project_dir, id)?.join(BINDING_FILE);
+    integrity::check_binding(project_dir, terraform_dir, &plan_path(id)?, &binding).await
+}
+
+async fn read_plan(project_dir: &Path, id: &str) -> Result<TerraformPlan, String> {
+    let path = plan_dir(project_dir, id)?.join(JSON_FILE);
+    let json = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|_| format!("Archived plan {} not found", id))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse archived plan {}: {}", id, e))
+}
+
+/// List archived plans, newest first.
+#[command]
+pub async fn terraform_plans_list(project_path: String) -> Result<Vec<ArchivedPlan>, String> {
+    let dir = Path::new(&project_path).join(ARCHIVE_DIR);
+    let mut entries = match tokio::fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e)),
+    };
+    let mut plans = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let id = entry.file_name().to_string_lossy().to_string();
+        match read_meta(Path::new(&project_path), &id).await {
+            Ok(meta) => plans.push(meta),
+            Err(e) => log::warn!("Skipping archived plan {}: {}", id, e),
+        }
+    }
+    // IDs start with a UTC timestamp, so they sort chronologically
+    plans.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(plans)
+}
+
+/// Read an archived plan's plain-text rendering.
+#[command]
+pub async fn terraform_plan_text(project_path: String, plan_id: String) -> Result<String, String> {
+    let path = plan_dir(Path::new(&project_path), &plan_id)?.join(TEXT_FILE);
+    tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|_| format!("Archived plan {} not found", plan_id))
+}
+
+/// Delete an archived plan.
+#[command]
+pub async fn terraform_plan_remove(project_path: String, plan_id: String) -> Result<bool, String> {
+    let dir = plan_dir(Path::new(&project_path), &plan_id)?;
+    match tokio::fs::remove_file(plan_path(&plan_id)?).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Failed to remove archived plan {}: {}", plan_id, e)),
+    }
+    tokio::fs::remove_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to remove archived plan {}: {}", plan_id, e))?;
+    Ok(true)
+}
+
+/// How one resource's planned change differs between two plans.
+#[derive(Clone, Debug, Serialize)]
+pub struct PlanDiffEntry {
+    pub address: String,
+    /// Actions in the first plan; `None` when it does not change the resource.
+    pub from_actions: Option<Vec<String>>,
+    pub to_actions: Option<Vec<String>>,
+    /// Top-level attributes whose planned values differ.
+    pub attributes: Vec<String>,
+}
+
+/// Resource-level differences between two archived plans.
+#[derive(Clone, Debug, Serialize)]
+pub struct PlanDiff {
+    pub from: String,
+    pub to: String,
+    /// Changed only by the second plan.
+    pub added: Vec<PlanDiffEntry>,
+    /// Changed only by the first plan.
+    pub removed: Vec<PlanDiffEntry>,
+    /// Changed by both, differently.
+    pub changed: Vec<PlanDiffEntry>,
+}
+
+/// Top-level keys whose values differ between two `after` objects.
+fn differing_attributes(from: Option<&serde_json::Value>, to: Option<&serde_json::Value>) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let from = from.and_then(|v| v.as_object()).unwrap_or(&empty);
+    let to = to.and_then(|v| v.as_object()).unwrap_or(&empty);
+    let mut keys: Vec<String> = from
+        .keys()
+        .chain(to.keys())
+        .filter(|k| from.get(*k) != to.get(*k))
+        .cloned()
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+fn diff_plans(from_id: &str, from: &TerraformPlan, to_id: &str, to: &TerraformPlan) -> PlanDiff {
+    let index = |plan: &TerraformPlan| -> BTreeMap<String, ResourceChange> {
+        plan.actionable_changes().map(|rc| (rc.address.clone(), rc.clone())).collect()
+    };
+    let from_changes = index(from);
+    let to_changes = index(to);
+
+    let mut diff = PlanDiff {
+        from: from_id.to_string(),
+        to: to_id.to_string(),
+        added: vec![],
+        removed: vec![],
+        changed: vec![],
+    };
+    for (address, a) in &from_changes {
+        match to_changes.get(address) {
+            None => diff.removed.push(PlanDiffEntry {
+                address: address.clone(),
+                from_actions: Some(a.change.actions.clone()),
+                to_actions: None,
+                attributes: vec![],
+            }),
+            Some(b) => {
+                let attributes = differing_attributes(a.change.after.as_ref(), b.change.after.as_ref());
+                if a.change.actions != b.change.actions || !attributes.is_empty() {
+                    diff.changed.push(PlanDiffEntry {
+                        address: address.clone(),
+                        from_actions: Some(a.change.actions.clone()),
+                        to_actions: Some(b.change.actions.clone()),
+                        attributes,
+                    });
+                }
+            }
+        }
+    }
+    for (address, b) in &to_changes {
+        if !from_changes.contains_key(address) {
+            diff.added.push(PlanDiffEntry {
+                address: address.clone(),
+                from_actions: None,
+                to_actions: Some(b.change.actions.clone()),
+                attributes: vec![],
+            });
+        }
+    }
+    diff
+}
+
+/// Diff two archived plans resource by resource.
+#[command]
+pub async fn terraform_plan_diff(project_path: String, from_id: String, to_id: String) -> Result<PlanDiff, String> {
+    let project_dir = Path::new(&project_path);
+    let from = read_plan(project_dir, &from_id).await?;
+    let to = read_plan(project_dir, &to_id).await?;
+    Ok(diff_plans(&from_id, &from, &to_id, &to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn redact_plan_json_drops_variables_and_masks_sensitive_values() {
+        let plan = json!({
+            "format_version": "1.2",
+            "variables": { "db_password": { "value": "hunter2" } },
+            "resource_changes": [{
+                "address": "azurerm_sql_server.main",
+                "type": "azurerm_sql_server",
+                "change": {
+                    "actions": ["create"],
+                    "before": null,
+                    "after": { "name": "sql", "administrator_login_password": "hunter2", "tags": { "a": "b" } },
+                    "after_sensitive": { "administrator_login_password": true, "tags": {} }
+                }
+            }],
+            "output_changes": {
+                "password": { "actions": ["create"], "before": null, "after": "hunter2", "after_sensitive": true }
+            },
+            "planned_values": {
+                "outputs": { "password": { "sensitive": true, "value": "hunter2" } },
+                "root_module": {
+                    "child_modules": [{
+                        "resources": [{
+                            "values": { "secret": "hunter2", "name": "x" },
+                            "sensitive_values": { "secret": true }
+                        }]
+                    }]
+                }
+            },
+            "configuration": {
+                "root_module": {
+                    "variables": {
+                        "db_password": { "default": "hunter2", "sensitive": true },
+                        "location": { "default": "westeurope" }
+                    }
+                }
+            }
+        });
+
+        let redacted = redact_plan_json(&plan.to_string()).unwrap();
+        assert!(!redacted.contains("hunter2"));
+        let redacted: serde_json::Value = serde_json::from_str(&redacted).unwrap();
+        assert!(redacted.get("variables").is_none());
+        let after = &redacted["resource_changes"][0]["change"]["after"];
+        assert_eq!(after["administrator_login_password"], SENSITIVE_VALUE);
+        assert_eq!(after["name"], "sql");
+        assert_eq!(after["tags"]["a"], "b");
+        assert_eq!(redacted["output_changes"]["password"]["after"], SENSITIVE_VALUE);
+        let resource = &redacted["planned_values"]["root_module"]["child_modules"][0]["resources"][0];
+        assert_eq!(resource["values"]["name"], "x");
+        assert_eq!(
+            redacted["configuration"]["root_module"]["variables"]["location"]["default"],
+            "westeurope"
+        );
+
+        // Still parses as a plan for listing and diffing
+        let parsed: TerraformPlan = serde_json::from_value(redacted).unwrap();
+        assert_eq!(parsed.resource_changes.len(), 1);
+    }
+}
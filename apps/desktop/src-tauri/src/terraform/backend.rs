@@ -0,0 +1,451 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use super::generated;
+use crate::project::commands::read_project_config;
+use crate::project::secrets;
+
+/// Backend file managed by TerraStudio inside the terraform directory.
+pub const BACKEND_FILE: &str = "backend.tf";
+
+/// Backends the app knows how to configure.
+const SUPPORTED_BACKENDS: &[&str] = &["azurerm", "local", "http"];
+
+/// First line of a managed backend file. Files without it are hand-written
+/// and are never overwritten or removed.
+const MANAGED_HEADER: &str = "# Managed by TerraStudio. Settings are passed to `terraform init` via -backend-config.";
+
+/// Backend settings that hold credentials. They are read from the user secrets
+/// store, never from the project file, which is usually committed.
+const CREDENTIAL_KEYS: &[&str] = &[
+    "access_key",
+    "sas_token",
+    "client_secret",
+    "client_certificate_password",
+    "oidc_token",
+    "oidc_request_token",
+    "password",
+];
+
+/// Backend settings from `projectConfig.backend`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BackendConfig {
+    #[serde(rename = "type")]
+    pub backend_type: String,
+    #[serde(default)]
+    pub config: BTreeMap<String, String>,
+}
+
+impl BackendConfig {
+    /// Check the backend type against the allow-list and validate every key/value.
+    pub fn validate(&self) -> Result<(), String> {
+        if !SUPPORTED_BACKENDS.contains(&self.backend_type.as_str()) {
+            return Err(format!(
+                "Unsupported backend type '{}' (expected one of: {})",
+                self.backend_type,
+                SUPPORTED_BACKENDS.join(", ")
+            ));
+        }
+        for (key, value) in &self.config {
+            let valid_key = !key.is_empty()
+                && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid_key {
+                return Err(format!("Invalid backend setting name: {}", key));
+            }
+            // Values may be credentials, so never echo them back
+            if value.chars().any(char::is_control) {
+                return Err(format!("Backend setting {} contains an invalid character", key));
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the credentials from the user secrets store to the project's settings.
+    pub fn merge_secrets(&mut self, secrets: HashMap<String, String>) -> Result<(), String> {
+        self.config.extend(secrets);
+        self.validate()
+    }
+
+    /// Contents of a `-backend-config` file setting every key, e.g. `path = "a.tfstate"`.
+    pub fn render_config_file(&self) -> String {
+        self.config
+            .iter()
+            .map(|(key, value)| {
+                // JSON string escaping is valid HCL once template sequences are escaped
+                let quoted = serde_json::to_string(value)
+                    .unwrap_or_default()
+                    .replace("${", "$${")
+                    .replace("%{", "%%{");
+                format!("{} = {}\n", key, quoted)
+            })
+            .collect()
+    }
+
+    /// Render a partial backend block. Only the validated type is written; all
+    /// settings (which may include access keys) stay out of the generated files.
+    pub fn render_block(&self) -> String {
+        format!(
+            "{}\nterraform {{\n  backend \"{}\" {{}}\n}}\n",
+            MANAGED_HEADER, self.backend_type
+        )
+    }
+}
+
+fn parse_backend_config(project_config: &serde_json::Value) -> Result<Option<BackendConfig>, String> {
+    let backend = match project_config.get("backend") {
+        None | Some(serde_json::Value::Null) => return Ok(None),
+        Some(value) => serde_json::from_value::<BackendConfig>(value.clone())
+            .map_err(|e| format!("Invalid backend configuration: {}", e))?,
+    };
+    backend.validate()?;
+    if let Some(key) = backend.config.keys().find(|k| CREDENTIAL_KEYS.contains(&k.as_str())) {
+        return Err(format!(
+            "Backend setting {} is a credential; store it in the backend secrets, not the project file",
+            key
+        ));
+    }
+    Ok(Some(backend))
+}
+
+/// Load and validate the backend configured for a project, if any.
+/// Only the settings from the project file; see [`read_init_config`].
+pub async fn read_backend_config(project_dir: &Path) -> Result<Option<BackendConfig>, String> {
+    parse_backend_config(&read_project_config(project_dir).await?)
+}
+
+/// The backend settings for `terraform init`: the project's settings plus the
+/// credentials stored for it in the user secrets store.
+pub async fn read_init_config(project_dir: &Path) -> Result<Option<BackendConfig>, String> {
+    let project_config = read_project_config(project_dir).await?;
+    let Some(mut backend) = parse_backend_config(&project_config)? else {
+        return Ok(None);
+    };
+    if let Some(secrets_id) = project_config.get("secretsId").and_then(|v| v.as_str()) {
+        backend.merge_secrets(secrets::read_backend_secrets(secrets_id).await?)?;
+    }
+    Ok(Some(backend))
+}
+
+/// A `-backend-config` file holding the settings for one `terraform init`.
+/// Written to the temp dir, readable by the user only, so credentials never
+/// appear on the command line or under the project; removed on drop.
+pub struct BackendConfigFile {
+    path: PathBuf,
+}
+
+impl BackendConfigFile {
+    pub async fn write(backend: &BackendConfig) -> Result<BackendConfigFile, String> {
+        use tokio::io::AsyncWriteExt;
+
+        let path = std::env::temp_dir().join(format!("terrastudio-backend-{}.tfbackend", uuid::Uuid::new_v4()));
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options
+            .open(&path)
+            .await
+            .map_err(|e| format!("Failed to create backend config file: {}", e))?;
+        // Owned from here on, so a failed write still removes the file
+        let config_file = BackendConfigFile { path };
+        file.write_all(backend.render_config_file().as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write backend config file: {}", e))?;
+        file.flush()
+            .await
+            .map_err(|e| format!("Failed to write backend config file: {}", e))?;
+        Ok(config_file)
+    }
+
+    /// The `-backend-config=<file>` flag for `terraform init`.
+    pub fn init_arg(&self) -> String {
+        format!("-backend-config={}", self.path.display())
+    }
+}
+
+impl Drop for BackendConfigFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Write the managed backend file, or remove it when no backend is configured.
+/// A hand-written `backend.tf` is left alone.
+pub async fn sync_backend_file(terraform_dir: &Path, backend: Option<&BackendConfig>) -> Result<(), String> {
+    let path = terraform_dir.join(BACKEND_FILE);
+    let existing = tokio::fs::read_to_string(&path).await.ok();
+    if let Some(content) = &existing {
+        if !content.starts_with(MANAGED_HEADER) {
+            if backend.is_some() {
+                log::warn!("Not overwriting hand-written {}", path.display());
+            }
+            return Ok(());
+        }
+    }
+
+    match backend {
+        Some(backend) => {
+            let content = backend.render_block();
+            if existing.as_deref() != Some(content.as_str()) {
+                generated::write_atomic(&path, content.as_bytes())
+                    .await
+                    .map_err(|e| format!("Failed to write {}: {}", BACKEND_FILE, e))?;
+            }
+        }
+        None => {
+            if existing.is_some() {
+                tokio::fs::remove_file(&path)
+                    .await
+                    .map_err(|e| format!("Failed to remove {}: {}", BACKEND_FILE, e))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn backend(backend_type: &str, config: &[(&str, &str)]) -> BackendConfig {
+        BackendConfig {
+            backend_type: backend_type.to_string(),
+            config: config.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        }
+    }
+
+    /// Fresh scratch directory, removed by the caller.
+    fn scratch_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("terrastudio-backend-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_project(dir: &Path, backend: serde_json::Value) {
+        let project = serde_json::json!({
+            "name": "test",
+            "version": "1",
+            "projectConfig": { "backend": backend },
+        });
+        std::fs::write(dir.join("test.tstudio"), project.to_string()).unwrap();
+    }
+
+    #[test]
+    fn validate_accepts_local_and_http() {
+        assert!(backend("local", &[("path", "state/terraform.tfstate")]).validate().is_ok());
+        assert!(backend("http", &[("address", "http://127.0.0.1:8080/state"), ("lock_method", "POST")])
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_unsupported_type() {
+        let err = backend("s3", &[]).validate().unwrap_err();
+        assert!(err.contains("Unsupported backend type 's3'"));
+    }
+
+    #[test]
+    fn validate_rejects_bad_keys_and_values() {
+        assert!(backend("local", &[("Path", "x")]).validate().is_err());
+        assert!(backend("local", &[("path=x", "y")]).validate().is_err());
+        assert!(backend("local", &[("", "y")]).validate().is_err());
+
+        let err = backend("http", &[("password", "secret\n-reconfigure")]).validate().unwrap_err();
+        assert!(err.contains("password"));
+        assert!(!err.contains("secret"));
+        assert!(backend("http", &[("password", "a\u{1b}b")]).validate().is_err());
+    }
+
+    #[test]
+    fn config_file_quotes_every_setting() {
+        let http = backend(
+            "http",
+            &[("address", "http://127.0.0.1:8080/state"), ("password", "p\"a\\s${x}%{y}")],
+        );
+        assert_eq!(
+            http.render_config_file(),
+            "address = \"http://127.0.0.1:8080/state\"\npassword = \"p\\\"a\\\\s$${x}%%{y}\"\n"
+        );
+        assert_eq!(backend("local", &[]).render_config_file(), "");
+    }
+
+    #[test]
+    fn merge_secrets_adds_and_validates_credentials() {
+        let mut http = backend("http", &[("address", "http://127.0.0.1/state"), ("username", "ci")]);
+        http.merge_secrets(HashMap::from([("password".to_string(), "hunter2".to_string())]))
+            .unwrap();
+        assert_eq!(http.config.get("password").map(String::as_str), Some("hunter2"));
+
+        let mut http = backend("http", &[]);
+        assert!(http
+            .merge_secrets(HashMap::from([("Bad Key".to_string(), "x".to_string())]))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn read_backend_config_validates() {
+        let dir = scratch_dir();
+
+        write_project(&dir, serde_json::Value::Null);
+        assert!(read_backend_config(&dir).await.unwrap().is_none());
+
+        write_project(&dir, serde_json::json!({ "type": "local", "config": { "path": "a.tfstate" } }));
+        let local = read_backend_config(&dir).await.unwrap().unwrap();
+        assert_eq!(local.backend_type, "local");
+        assert_eq!(local.config.get("path").map(String::as_str), Some("a.tfstate"));
+
+        write_project(&dir, serde_json::json!({ "type": "remote" }));
+        assert!(read_backend_config(&dir).await.is_err());
+
+        write_project(&dir, serde_json::json!({ "config": {} }));
+        assert!(read_backend_config(&dir).await.unwrap_err().contains("Invalid backend configuration"));
+
+        // Credentials belong in the secrets store
+        write_project(&dir, serde_json::json!({ "type": "http", "config": { "password": "hunter2" } }));
+        let err = read_backend_config(&dir).await.unwrap_err();
+        assert!(err.contains("password") && !err.contains("hunter2"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn sync_backend_file_writes_only_the_type() {
+        let dir = scratch_dir();
+        let path = dir.join(BACKEND_FILE);
+        let http = backend("http", &[("address", "http://127.0.0.1:8080/state"), ("password", "hunter2")]);
+
+        sync_backend_file(&dir, Some(&http)).await.unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, format!("{}\nterraform {{\n  backend \"http\" {{}}\n}}\n", MANAGED_HEADER));
+        assert!(!content.contains("hunter2"));
+
+        sync_backend_file(&dir, Some(&backend("local", &[]))).await.unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("backend \"local\" {}"));
+
+        sync_backend_file(&dir, None).await.unwrap();
+        assert!(!path.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn sync_backend_file_leaves_hand_written_file() {
+        let dir = scratch_dir();
+        let path = dir.join(BACKEND_FILE);
+        let hand_written = "terraform {\n  backend \"local\" {}\n}\n";
+        std::fs::write(&path, hand_written).unwrap();
+
+        sync_backend_file(&dir, Some(&backend("http", &[]))).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), hand_written);
+        sync_backend_file(&dir, None).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), hand_written);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Whether a terraform binary is on PATH; the init tests are skipped without one.
+    fn terraform_available() -> bool {
+        std::process::Command::new("terraform")
+            .arg("version")
+            .output()
+            .is_ok_and(|o| o.status.success())
+    }
+
+    /// `terraform init` in `dir` with the generated backend block and config file,
+    /// as `terraform_init` runs it.
+    async fn init(dir: &Path, backend: &BackendConfig) -> std::process::Output {
+        sync_backend_file(dir, Some(backend)).await.unwrap();
+        let config_file = BackendConfigFile::write(backend).await.unwrap();
+        let output = tokio::process::Command::new("terraform")
+            .args(["init", "-no-color", "-input=false", &config_file.init_arg()])
+            .current_dir(dir)
+            .env("TF_IN_AUTOMATION", "1")
+            .output()
+            .await
+            .unwrap();
+        let path = config_file.path.clone();
+        drop(config_file);
+        assert!(!path.exists());
+        output
+    }
+
+    /// Backend settings terraform recorded in `.terraform/terraform.tfstate`.
+    fn initialized_backend(dir: &Path) -> serde_json::Value {
+        let state = std::fs::read_to_string(dir.join(".terraform").join("terraform.tfstate")).unwrap();
+        serde_json::from_str::<serde_json::Value>(&state).unwrap()["backend"].clone()
+    }
+
+    #[tokio::test]
+    async fn init_configures_local_backend_offline() {
+        if !terraform_available() {
+            eprintln!("terraform not found; skipping");
+            return;
+        }
+        let dir = scratch_dir();
+        let local = backend("local", &[("path", "state/custom.tfstate")]);
+
+        let output = init(&dir, &local).await;
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        let configured = initialized_backend(&dir);
+        assert_eq!(configured["type"], "local");
+        assert_eq!(configured["config"]["path"], "state/custom.tfstate");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn init_passes_credentials_to_http_backend() {
+        use std::io::{BufRead, BufReader, Write};
+
+        if !terraform_available() {
+            eprintln!("terraform not found; skipping");
+            return;
+        }
+        // Minimal HTTP backend stand-in: no state yet, and only for the right credentials
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}/state", listener.local_addr().unwrap());
+        let authorized = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let authorized_count = authorized.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut auth = false;
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 0) && line.trim_end() != "" {
+                    // base64("ci:hunter2")
+                    auth |= line.trim_end().eq_ignore_ascii_case("authorization: basic Y2k6aHVudGVyMg==");
+                    line.clear();
+                }
+                let status = if auth {
+                    authorized_count.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    "404 Not Found"
+                } else {
+                    "401 Unauthorized"
+                };
+                let _ = (&stream).write_all(
+                    format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).as_bytes(),
+                );
+            }
+        });
+
+        let dir = scratch_dir();
+        let mut http = backend("http", &[("address", &address), ("username", "ci")]);
+        http.merge_secrets(HashMap::from([("password".to_string(), "hunter2".to_string())]))
+            .unwrap();
+
+        let output = init(&dir, &http).await;
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert!(authorized.load(std::sync::atomic::Ordering::SeqCst) > 0);
+        assert_eq!(initialized_backend(&dir)["type"], "http");
+
+        // Without the password the stand-in refuses, and so does init
+        let dir_without = scratch_dir();
+        let output = init(&dir_without, &backend("http", &[("address", &address), ("username", "ci")])).await;
+        assert!(!output.status.success());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&dir_without).unwrap();
+    }
+}
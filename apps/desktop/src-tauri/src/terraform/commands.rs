@@ -1,18 +1,70 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use tauri::{command, AppHandle, WebviewWindow};
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Manager, WebviewWindow};
 
-use super::runner::{self, TerraformJsonResult, TerraformPlanResult};
+use super::archive;
+use super::backend;
+use super::destroy::{self, DestroyPlanResult, DestroyTokens, DESTROY_PLAN_FILE};
+use super::diagram::{self, DiagramImport, DiagramTypeHint};
+use super::drift::{self, TerraformDriftResult};
+use super::engine::{self, Engine, EngineInfo, EngineKind};
+use super::format;
+use super::generated;
+use super::import::{self, ImportSpec};
+use super::integrity;
+use super::lock::{LockHolder, ProjectLockGuard, ProjectLocks};
+use super::moved;
+use super::policy;
+use super::runner::{self, RunContext, TerraformDiagnostic, TerraformJsonResult, TerraformPlanResult};
+use super::settings;
+use super::state::{self, StateResource, TerraformState};
+use super::workspace;
+use crate::process::{ProcessKind, ProcessRegistry, RunningProcess};
+use crate::project::commands::read_project_name;
+use crate::project::secrets;
 use crate::security;
 
-/// Write generated .tf files to the project's terraform/ directory.
+/// Saved plan file written by `terraform_plan_with_out`, relative to the terraform dir.
+const PLAN_FILE: &str = "tfplan";
+
+/// Scratch plan file for drift detection. Kept under `.terraform/` so it is never
+/// mistaken for a reviewable plan and stays out of version control.
+const DRIFT_PLAN_FILE: &str = ".terraform/terrastudio-drift.tfplan";
+
+/// Scratch plan file for `terraform_apply`, which plans and applies in one step.
+const APPLY_PLAN_FILE: &str = ".terraform/terrastudio-apply.tfplan";
+
+/// Result of `write_terraform_files`. When any file fails to parse, nothing is
+/// written and `diagnostics` lists the syntax errors.
+#[derive(Clone, Serialize)]
+pub struct TerraformWriteResult {
+    pub success: bool,
+    pub path: String,
+    pub diagnostics: Vec<TerraformDiagnostic>,
+    /// Files whose content changed; unchanged files are not rewritten.
+    pub written: Vec<String>,
+    /// Files from the previous generation that are no longer produced.
+    pub removed: Vec<String>,
+}
+
+/// Write generated .tf files to the project's terraform root (`terraform/` unless
+/// `projectConfig.terraform.root` names another directory).
 /// Supports subdirectory paths (e.g., "modules/net/main.tf") for module output.
+/// HCL files are syntax-checked and canonically formatted first; if any fails
+/// to parse, no file is written. Unchanged files are skipped, the rest are
+/// replaced atomically, and files left over from the previous generation are
+/// removed.
+/// `addresses` maps node IDs to resource addresses; nodes whose address changed
+/// since the previous write get `moved` blocks in `moved.tf`.
 #[command]
 pub async fn write_terraform_files(
     project_path: String,
     files: HashMap<String, String>,
-) -> Result<String, String> {
-    let terraform_dir = PathBuf::from(&project_path).join("terraform");
+    addresses: Option<HashMap<String, String>>,
+) -> Result<TerraformWriteResult, String> {
+    let terraform_dir = settings::terraform_dir(Path::new(&project_path)).await?;
+    let path = terraform_dir.to_string_lossy().to_string();
 
     // Validate all file paths before writing any files
     for filepath in files.keys() {
@@ -20,37 +72,382 @@ pub async fn write_terraform_files(
             .map_err(|e| format!("Invalid terraform file path: {}", e))?;
     }
 
+    // Check and format every HCL file up front so a syntax error leaves the
+    // previous files untouched
+    let mut formatted: Vec<(&String, String)> = Vec::with_capacity(files.len());
+    let mut diagnostics = Vec::new();
+    let mut names: Vec<&String> = files.keys().collect();
+    names.sort();
+    for filepath in names {
+        let content = &files[filepath];
+        if !format::is_hcl_file(filepath) {
+            formatted.push((filepath, content.clone()));
+            continue;
+        }
+        match format::check_and_format(filepath, content) {
+            Ok(content) => formatted.push((filepath, content)),
+            Err(diagnostic) => diagnostics.push(*diagnostic),
+        }
+    }
+    if !diagnostics.is_empty() {
+        return Ok(TerraformWriteResult {
+            success: false,
+            path,
+            diagnostics,
+            written: Vec::new(),
+            removed: Vec::new(),
+        });
+    }
+
+    // Likewise validate renamed addresses and read the backend config before
+    // anything is written, so an error cannot leave the tree half-updated
+    let project_dir = PathBuf::from(&project_path);
+    let pending_moves = match &addresses {
+        Some(addresses) => Some(moved::plan_moved_blocks(&project_dir, addresses).await?),
+        None => None,
+    };
+    let backend = backend::read_backend_config(&project_dir).await?;
+
     tokio::fs::create_dir_all(&terraform_dir)
         .await
         .map_err(|e| format!("Failed to create terraform directory: {}", e))?;
 
-    for (filepath, content) in &files {
-        let path = terraform_dir.join(filepath);
-        // Create subdirectories if the path contains them (e.g., modules/net/)
-        if let Some(parent) = path.parent() {
-            tokio::fs::create_dir_all(parent)
-                .await
-                .map_err(|e| format!("Failed to create directory for {}: {}", filepath, e))?;
+    let summary = generated::write_generated_files(&project_dir, &terraform_dir, &formatted).await?;
+    log::info!(
+        "Terraform files: {} written, {} unchanged, {} removed",
+        summary.written.len(),
+        summary.unchanged.len(),
+        summary.removed.len()
+    );
+
+    if let Some(pending_moves) = pending_moves {
+        let moves = pending_moves.write(&project_dir, &terraform_dir).await?;
+        if !moves.is_empty() {
+            log::info!("Wrote {} moved block(s) for renamed resources", moves.len());
+        }
+    }
+
+    // Keep the managed backend block in step with the project's backend config
+    backend::sync_backend_file(&terraform_dir, backend.as_ref()).await?;
+
+    Ok(TerraformWriteResult {
+        success: true,
+        path,
+        diagnostics,
+        written: summary.written,
+        removed: summary.removed,
+    })
+}
+
+// --- Sanitization helpers ---
+
+/// Validate a terraform resource address, e.g. `azurerm_linux_web_app.api`,
+/// `data.azurerm_client_config.current`, `module.net.azurerm_subnet.app["web"]`
+/// or a whole module instance such as `module.vm[0]`.
+/// Grammar: `(module.NAME[INDEX]?.)* ((data.)? TYPE.NAME[INDEX]?)?`, with at
+/// least one module step when the resource part is omitted.
+pub(super) fn sanitize_address(address: &str) -> Result<&str, String> {
+    if address.is_empty() {
+        return Err("Resource address cannot be empty".to_string());
+    }
+    if address.contains('\0') {
+        return Err("Resource address contains null byte".to_string());
+    }
+    if address.len() > 512 {
+        return Err(format!("Resource address is too long: {}", address));
+    }
+    if address.starts_with('-') {
+        return Err(format!("Resource address cannot start with '-': {}", address));
+    }
+
+    let steps = split_address_steps(address)?;
+    let invalid = || format!("Invalid resource address: {}", address);
+
+    let mut i = 0;
+    let mut module_steps = 0;
+    while i < steps.len() && steps[i].0 == "module" && steps[i].1.is_none() {
+        // module.NAME[INDEX]?
+        let (name, _) = steps.get(i + 1).ok_or_else(invalid)?;
+        validate_identifier(name).map_err(|_| invalid())?;
+        module_steps += 1;
+        i += 2;
+    }
+
+    let rest = &steps[i..];
+    let resource = match rest {
+        [] if module_steps > 0 => return Ok(address),
+        [(data, None), rtype, name] if *data == "data" => [rtype, name],
+        [rtype, name] if rtype.0 != "data" => [rtype, name],
+        _ => return Err(invalid()),
+    };
+    let [(rtype, rtype_index), (name, _)] = resource;
+    if rtype_index.is_some() {
+        return Err(invalid());
+    }
+    validate_identifier(rtype).map_err(|_| invalid())?;
+    validate_identifier(name).map_err(|_| invalid())?;
+
+    Ok(address)
+}
+
+/// Validate an address that must name a single managed resource (optionally
+/// inside modules), as required by `moved` and `import` blocks.
+pub(super) fn sanitize_managed_address(address: &str) -> Result<&str, String> {
+    sanitize_address(address)?;
+    let steps = split_address_steps(address)?;
+    let mut i = 0;
+    while i < steps.len() && steps[i].0 == "module" && steps[i].1.is_none() {
+        i += 2;
+    }
+    match steps.len().saturating_sub(i) {
+        0 => Err(format!("Expected a resource address, not a module: {}", address)),
+        3 => Err(format!("Expected a managed resource, not a data source: {}", address)),
+        _ => Ok(address),
+    }
+}
+
+/// Split an address into `(name, index)` steps on dots outside quoted keys,
+/// validating each index as a number or a quoted string key.
+fn split_address_steps(address: &str) -> Result<Vec<(&str, Option<&str>)>, String> {
+    let mut raw_steps = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    for (pos, ch) in address.char_indices() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            '.' if !in_quotes => {
+                raw_steps.push(&address[start..pos]);
+                start = pos + 1;
+            }
+            _ => {}
+        }
+    }
+    if in_quotes {
+        return Err(format!("Resource address has an unterminated key: {}", address));
+    }
+    raw_steps.push(&address[start..]);
+
+    raw_steps
+        .into_iter()
+        .map(|step| match step.find('[') {
+            None => Ok((step, None)),
+            Some(open) => {
+                let index = step[open..]
+                    .strip_prefix('[')
+                    .and_then(|s| s.strip_suffix(']'))
+                    .ok_or_else(|| format!("Invalid index in resource address: {}", address))?;
+                validate_index(index).map_err(|e| format!("{}: {}", e, address))?;
+                Ok((&step[..open], Some(index)))
+            }
+        })
+        .collect()
+}
+
+/// Terraform identifiers: a letter or underscore, then letters, digits, underscores or hyphens.
+fn validate_identifier(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return Err(format!("Invalid identifier: {}", name)),
+    }
+    if chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        Ok(())
+    } else {
+        Err(format!("Invalid identifier: {}", name))
+    }
+}
+
+/// An instance key: a count index (`0`) or a quoted for_each key (`"web"`).
+/// Quoted keys allow alphanumerics, spaces and `-_.:/@`.
+fn validate_index(index: &str) -> Result<(), String> {
+    if !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(());
+    }
+    let key = index
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(|| "Instance key must be a number or a quoted string".to_string())?;
+    for ch in key.chars() {
+        if !ch.is_alphanumeric() && !matches!(ch, ' ' | '-' | '_' | '.' | ':' | '/' | '@') {
+            return Err(format!("Instance key contains invalid character '{}'", ch));
+        }
+    }
+    Ok(())
+}
+
+/// Build `-target=`/`-replace=` flags from optional lists of resource addresses.
+fn address_args(targets: Option<&[String]>, replace: Option<&[String]>) -> Result<Vec<String>, String> {
+    let targets = targets
+        .unwrap_or_default()
+        .iter()
+        .map(|t| sanitize_address(t).map(|a| format!("-target={}", a)));
+    let replace = replace
+        .unwrap_or_default()
+        .iter()
+        .map(|r| sanitize_address(r).map(|a| format!("-replace={}", a)));
+    targets.chain(replace).collect()
+}
+
+/// Variable and workspace settings shared by the plan/apply/destroy commands.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunOptions {
+    /// Project secrets to hand to terraform as `TF_VAR_*` environment variables.
+    #[serde(default)]
+    pub secrets_id: Option<String>,
+    /// `.tfvars`/`.tfvars.json` files, relative to the terraform directory.
+    #[serde(default)]
+    pub var_files: Option<Vec<String>>,
+    /// Workspace for this run only; defaults to the selected workspace.
+    #[serde(default)]
+    pub workspace: Option<String>,
+}
+
+/// Build `-var-file=` flags, validating each path.
+fn var_file_args(options: &RunOptions) -> Result<Vec<String>, String> {
+    options
+        .var_files
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .map(|f| {
+            let path = security::sanitize_filepath(f)
+                .map_err(|e| format!("Invalid var file path: {}", e))?;
+            if !path.ends_with(".tfvars") && !path.ends_with(".tfvars.json") {
+                return Err(format!("Var file must be a .tfvars or .tfvars.json file: {}", path));
+            }
+            Ok(format!("-var-file={}", path))
+        })
+        .collect()
+}
+
+/// Build the run context for a terraform run. The project's secrets are handed to
+/// the child process only as `TF_VAR_*` environment variables — never written under
+/// the project directory. Error messages name the variable but never its value.
+async fn run_context(project_path: &str, options: &RunOptions) -> Result<RunContext, String> {
+    let mut ctx = RunContext {
+        workspace: options
+            .workspace
+            .as_deref()
+            .map(|w| workspace::sanitize_workspace_name(w).map(str::to_string))
+            .transpose()?,
+        ..RunContext::for_project(Path::new(project_path)).await?
+    };
+    if let Some(id) = &options.secrets_id {
+        for (name, value) in secrets::read_secrets(id).await? {
+            validate_identifier(&name)
+                .map_err(|_| format!("Invalid variable name in secrets: {}", name))?;
+            if value.contains('\0') {
+                return Err(format!("Secret value for variable {} contains null byte", name));
+            }
+            ctx.env.push((format!("TF_VAR_{}", name), value));
         }
-        tokio::fs::write(&path, content)
-            .await
-            .map_err(|e| format!("Failed to write {}: {}", filepath, e))?;
     }
+    Ok(ctx)
+}
 
-    Ok(terraform_dir.to_string_lossy().to_string())
+/// Run context for commands that only need a workspace (no variables).
+async fn workspace_context(project_path: &str, workspace: Option<String>) -> Result<RunContext, String> {
+    run_context(project_path, &RunOptions {
+        workspace,
+        ..RunOptions::default()
+    })
+    .await
 }
 
-/// Check if terraform is installed and return version string.
+/// Borrow owned CLI arguments as the `&[&str]` slice the runner expects.
+fn as_strs(args: &[String]) -> Vec<&str> {
+    args.iter().map(String::as_str).collect()
+}
+
+/// Take the per-project run lock for the calling window.
+/// Hold the returned guard for the duration of the terraform run.
+pub(super) fn lock_project(
+    app: &AppHandle,
+    window: &WebviewWindow,
+    project_path: &str,
+    command: &str,
+) -> Result<ProjectLockGuard, String> {
+    app.state::<ProjectLocks>().acquire(project_path, window.label(), command)
+}
+
+/// Engines found on this machine, and which one the project runs with.
+#[derive(Clone, Serialize)]
+pub struct EngineReport {
+    pub selected: EngineKind,
+    pub engines: Vec<EngineInfo>,
+}
+
+/// Probe terraform and OpenTofu and report their versions and capabilities.
+/// With a project, its configured engine (and binary path) is probed in place
+/// of the PATH default.
 #[command]
-pub async fn check_terraform() -> Result<String, String> {
-    runner::check_terraform_installed().await
+pub async fn check_terraform(project_path: Option<String>) -> Result<EngineReport, String> {
+    let selected = match &project_path {
+        Some(project_path) => engine::read_engine(Path::new(project_path)).await?,
+        None => Engine::default(),
+    };
+    let mut engines = Vec::new();
+    for kind in EngineKind::ALL {
+        let candidate = if kind == selected.kind {
+            selected.clone()
+        } else {
+            Engine::on_path(kind)
+        };
+        engines.push(engine::detect(&candidate).await);
+    }
+    Ok(EngineReport {
+        selected: selected.kind,
+        engines,
+    })
 }
 
 /// Run terraform init in the project's terraform/ directory.
+/// The backend from `projectConfig.backend`, with its credentials from the user
+/// secrets store, is passed in a temporary `-backend-config` file.
+/// `reconfigure` discards the existing backend setup; `migrate_state` copies
+/// existing state into the newly configured backend.
 #[command]
-pub async fn terraform_init(app: AppHandle, window: WebviewWindow, project_path: String) -> Result<bool, String> {
-    let terraform_dir = PathBuf::from(&project_path).join("terraform");
-    let result = runner::run_terraform(&app, window.label(), &terraform_dir, "init", &["-no-color"]).await?;
+pub async fn terraform_init(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    reconfigure: Option<bool>,
+    migrate_state: Option<bool>,
+) -> Result<bool, String> {
+    let reconfigure = reconfigure.unwrap_or(false);
+    let migrate_state = migrate_state.unwrap_or(false);
+    if reconfigure && migrate_state {
+        return Err("reconfigure and migrateState cannot be used together".to_string());
+    }
+
+    let _lock = lock_project(&app, &window, &project_path, "init")?;
+    let ctx = RunContext::for_project(Path::new(&project_path)).await?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+
+    let backend = backend::read_init_config(&PathBuf::from(&project_path)).await?;
+    backend::sync_backend_file(&terraform_dir, backend.as_ref()).await?;
+
+    let mut args = vec!["-no-color".to_string(), "-input=false".to_string()];
+    // Held until init exits; dropping it removes the file
+    let config_file = match &backend {
+        Some(backend) => Some(backend::BackendConfigFile::write(backend).await?),
+        None => None,
+    };
+    if let Some(config_file) = &config_file {
+        args.push(config_file.init_arg());
+    }
+    if reconfigure {
+        args.push("-reconfigure".to_string());
+    }
+    if migrate_state {
+        // Without -force-copy terraform would prompt before copying state
+        args.push("-migrate-state".to_string());
+        args.push("-force-copy".to_string());
+    }
+    args.extend(ctx.settings.flags("init"));
+
+    let result = runner::run_terraform(&app, window.label(), &terraform_dir, &ctx, "init", &as_strs(&args)).await?;
     Ok(result.success)
 }
 
@@ -58,53 +455,590 @@ pub async fn terraform_init(app: AppHandle, window: WebviewWindow, project_path:
 /// Note: `terraform validate -json` outputs a single JSON blob, not streaming lines.
 #[command]
 pub async fn terraform_validate(app: AppHandle, window: WebviewWindow, project_path: String) -> Result<TerraformJsonResult, String> {
-    let terraform_dir = PathBuf::from(&project_path).join("terraform");
-    runner::run_terraform_validate_json(&app, window.label(), &terraform_dir).await
+    let ctx = RunContext::for_project(Path::new(&project_path)).await?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    runner::run_terraform_validate_json(&app, window.label(), &terraform_dir, &ctx).await
 }
 
 /// Run terraform plan with JSON output for structured error parsing.
+/// `targets` optionally limits the plan to the given resource addresses;
+/// `replace` forces recreation of the given resources.
+/// `options` supplies variables (secrets only via the environment, never on disk)
+/// and the workspace for this run.
 #[command]
-pub async fn terraform_plan(app: AppHandle, window: WebviewWindow, project_path: String) -> Result<TerraformJsonResult, String> {
-    let terraform_dir = PathBuf::from(&project_path).join("terraform");
-    runner::run_terraform_json(&app, window.label(), &terraform_dir, "plan", &[]).await
+pub async fn terraform_plan(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    targets: Option<Vec<String>>,
+    replace: Option<Vec<String>>,
+    options: Option<RunOptions>,
+) -> Result<TerraformJsonResult, String> {
+    let options = options.unwrap_or_default();
+    let mut args = address_args(targets.as_deref(), replace.as_deref())?;
+    args.extend(var_file_args(&options)?);
+    let ctx = run_context(&project_path, &options).await?;
+    args.extend(ctx.settings.flags("plan"));
+    let _lock = lock_project(&app, &window, &project_path, "plan")?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    runner::run_terraform_json(&app, window.label(), &terraform_dir, &ctx, "plan", &as_strs(&args)).await
+}
+
+/// Plan and apply in one step. The plan is saved to a scratch file and goes
+/// through the same staleness and policy checks as `terraform_apply_plan`, so
+/// nothing is applied that those checks would refuse.
+/// `targets` optionally limits the apply to the given resource addresses;
+/// `replace` forces recreation of the given resources.
+/// `options` behaves as for `terraform_plan`.
+#[command]
+pub async fn terraform_apply(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    targets: Option<Vec<String>>,
+    replace: Option<Vec<String>>,
+    options: Option<RunOptions>,
+) -> Result<TerraformJsonResult, String> {
+    let options = options.unwrap_or_default();
+    let mut args = address_args(targets.as_deref(), replace.as_deref())?;
+    args.extend(var_file_args(&options)?);
+    let ctx = run_context(&project_path, &options).await?;
+    args.extend(ctx.settings.flags("apply"));
+    let _lock = lock_project(&app, &window, &project_path, "apply")?;
+    let project_dir = Path::new(&project_path);
+    let terraform_dir = ctx.settings.terraform_dir(project_dir);
+    let planned =
+        runner::run_terraform_json_plan(&app, window.label(), &terraform_dir, &ctx, APPLY_PLAN_FILE, &as_strs(&args))
+            .await;
+    let plan_file = terraform_dir.join(APPLY_PLAN_FILE);
+    let result = match planned {
+        Ok(planned) if !planned.success => Ok(TerraformJsonResult {
+            success: false,
+            code: planned.code,
+            diagnostics: planned.diagnostics,
+            resource_changes: vec![],
+            stale_plan: None,
+        }),
+        Ok(_) => match integrity::check(project_dir, &terraform_dir, APPLY_PLAN_FILE).await {
+            Ok(Some(stale)) => Ok(integrity::stale_result(stale)),
+            Ok(None) => apply_checked_plan(&app, &window, project_dir, &terraform_dir, &ctx, &plan_file).await,
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+    let _ = tokio::fs::remove_file(&plan_file).await;
+    integrity::forget(project_dir, APPLY_PLAN_FILE).await;
+    result
 }
 
-/// Run terraform apply with auto-approve and JSON output.
+/// First step of a destroy: save a `plan -destroy` and list the resources it
+/// would delete. A successful plan returns a one-time token that
+/// `terraform_destroy_apply` requires, together with the project name typed back.
+/// `targets` optionally limits the destroy to the given resource addresses.
+/// `options` behaves as for `terraform_plan`.
 #[command]
-pub async fn terraform_apply(app: AppHandle, window: WebviewWindow, project_path: String) -> Result<TerraformJsonResult, String> {
-    let terraform_dir = PathBuf::from(&project_path).join("terraform");
-    runner::run_terraform_json(&app, window.label(), &terraform_dir, "apply", &["-auto-approve"]).await
+pub async fn terraform_destroy_plan(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    targets: Option<Vec<String>>,
+    options: Option<RunOptions>,
+) -> Result<DestroyPlanResult, String> {
+    let options = options.unwrap_or_default();
+    let mut args = vec!["-destroy".to_string()];
+    args.extend(address_args(targets.as_deref(), None)?);
+    args.extend(var_file_args(&options)?);
+    let ctx = run_context(&project_path, &options).await?;
+    args.extend(ctx.settings.flags("destroy"));
+    let project_name = read_project_name(Path::new(&project_path)).await?;
+    let _lock = lock_project(&app, &window, &project_path, "destroy")?;
+    let tokens = app.state::<DestroyTokens>();
+    tokens.revoke(&project_path);
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    let result = runner::run_terraform_json_plan(
+        &app,
+        window.label(),
+        &terraform_dir,
+        &ctx,
+        DESTROY_PLAN_FILE,
+        &as_strs(&args),
+    )
+    .await?;
+
+    let resources = result
+        .plan_changes
+        .iter()
+        .filter(|c| c.actions.iter().any(|a| a == "delete"))
+        .map(|c| c.address.clone())
+        .collect();
+    Ok(DestroyPlanResult {
+        success: result.success,
+        code: result.code,
+        diagnostics: result.diagnostics,
+        resources,
+        token: result.success.then(|| tokens.issue(&project_path, ctx.workspace.clone())),
+        project_name,
+        expires_in_secs: destroy::TOKEN_TTL.as_secs(),
+    })
 }
 
-/// Run terraform destroy with auto-approve and JSON output.
+/// Second step of a destroy: apply the plan saved by `terraform_destroy_plan`.
+/// Runs only with that plan's token and the project name typed back; the token
+/// is consumed, and the plan is refused as stale if the configuration changed
+/// in between.
 #[command]
-pub async fn terraform_destroy(app: AppHandle, window: WebviewWindow, project_path: String) -> Result<TerraformJsonResult, String> {
-    let terraform_dir = PathBuf::from(&project_path).join("terraform");
-    runner::run_terraform_json(&app, window.label(), &terraform_dir, "destroy", &["-auto-approve"]).await
+pub async fn terraform_destroy_apply(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    token: String,
+    confirm_name: String,
+) -> Result<TerraformJsonResult, String> {
+    let project_dir = Path::new(&project_path);
+    let project_name = read_project_name(project_dir).await?;
+    if confirm_name.trim() != project_name {
+        return Err(format!("Typed name does not match the project name '{}'", project_name));
+    }
+    let _lock = lock_project(&app, &window, &project_path, "destroy")?;
+    let workspace = app.state::<DestroyTokens>().redeem(&project_path, &token)?;
+    let ctx = workspace_context(&project_path, workspace).await?;
+    let terraform_dir = ctx.settings.terraform_dir(project_dir);
+    let plan_file = terraform_dir.join(DESTROY_PLAN_FILE);
+    let result = match integrity::check(project_dir, &terraform_dir, DESTROY_PLAN_FILE).await? {
+        Some(stale) => Ok(integrity::stale_result(stale)),
+        None => apply_checked_plan(&app, &window, project_dir, &terraform_dir, &ctx, &plan_file).await,
+    };
+    // The token is spent either way, so the plan can never be applied again
+    let _ = tokio::fs::remove_file(&plan_file).await;
+    integrity::forget(project_dir, DESTROY_PLAN_FILE).await;
+    result
 }
 
 /// Run terraform plan with JSON output, saving plan to file for later apply.
 /// Returns extended result with full before/after property diffs.
+/// `targets` optionally limits the plan to the given resource addresses;
+/// `replace` forces recreation of the given resources.
+/// `options` behaves as for `terraform_plan`.
+#[command]
+pub async fn terraform_plan_with_out(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    targets: Option<Vec<String>>,
+    replace: Option<Vec<String>>,
+    options: Option<RunOptions>,
+) -> Result<TerraformPlanResult, String> {
+    let options = options.unwrap_or_default();
+    let mut args = address_args(targets.as_deref(), replace.as_deref())?;
+    args.extend(var_file_args(&options)?);
+    let ctx = run_context(&project_path, &options).await?;
+    args.extend(ctx.settings.flags("plan"));
+    let _lock = lock_project(&app, &window, &project_path, "plan")?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    let result = runner::run_terraform_json_plan(&app, window.label(), &terraform_dir, &ctx, PLAN_FILE, &as_strs(&args)).await?;
+    Ok(archive_saved_plan(&project_path, &terraform_dir, &ctx, result, "plan").await)
+}
+
+/// Keep a successful saved plan in the plan archive. Archiving failures are
+/// logged; the plan itself is still usable as `tfplan`.
+async fn archive_saved_plan(
+    project_path: &str,
+    terraform_dir: &Path,
+    ctx: &RunContext,
+    mut result: TerraformPlanResult,
+    command: &str,
+) -> TerraformPlanResult {
+    if result.success {
+        match archive::archive_plan(Path::new(project_path), terraform_dir, ctx, PLAN_FILE, command).await {
+            Ok(id) => result.archive_id = Some(id),
+            Err(e) => log::warn!("Failed to archive plan: {}", e),
+        }
+    }
+    result
+}
+
+/// Write `import` blocks for existing resources and save a plan showing what
+/// will be imported; the user applies it with `terraform_apply_plan`.
+/// With `generate_config`, terraform writes HCL for imported resources that have
+/// no configuration yet into `generated.tf`.
+/// `options` behaves as for `terraform_plan`.
+#[command]
+pub async fn terraform_import_plan(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    imports: Vec<ImportSpec>,
+    generate_config: Option<bool>,
+    options: Option<RunOptions>,
+) -> Result<TerraformPlanResult, String> {
+    let options = options.unwrap_or_default();
+    let mut args = var_file_args(&options)?;
+    let ctx = run_context(&project_path, &options).await?;
+    args.extend(ctx.settings.flags("plan"));
+    let _lock = lock_project(&app, &window, &project_path, "import")?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+
+    // Every check runs before imports.tf is touched, so a rejected call leaves no trace
+    let info = engine::detect(&ctx.engine).await;
+    if !info.installed {
+        return Err(info
+            .error
+            .unwrap_or_else(|| format!("{} is not installed", info.name)));
+    }
+    if !info.capabilities.import_blocks {
+        return Err(format!(
+            "{} {} does not support import blocks; version 1.5 or later is required",
+            info.name,
+            info.version.unwrap_or_default()
+        ));
+    }
+    if generate_config.unwrap_or(false) {
+        // Terraform refuses to overwrite an existing file here
+        if terraform_dir.join(import::GENERATED_CONFIG_FILE).exists() {
+            return Err(format!(
+                "{} already exists; merge or delete it before generating configuration again",
+                import::GENERATED_CONFIG_FILE
+            ));
+        }
+        args.push(format!("-generate-config-out={}", import::GENERATED_CONFIG_FILE));
+    }
+    import::write_imports(&terraform_dir, &imports).await?;
+
+    let result = runner::run_terraform_json_plan(&app, window.label(), &terraform_dir, &ctx, PLAN_FILE, &as_strs(&args)).await?;
+    Ok(archive_saved_plan(&project_path, &terraform_dir, &ctx, result, "import").await)
+}
+
+/// Detect out-of-band changes by running a refresh-only plan.
+/// Progress streams through the usual `terraform:json` events; the result lists
+/// drifted resources with attribute-level before/after values.
+/// `options` behaves as for `terraform_plan`.
+#[command]
+pub async fn terraform_detect_drift(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    options: Option<RunOptions>,
+) -> Result<TerraformDriftResult, String> {
+    let options = options.unwrap_or_default();
+    let mut args = vec!["-refresh-only".to_string()];
+    args.extend(var_file_args(&options)?);
+    let ctx = run_context(&project_path, &options).await?;
+    args.extend(ctx.settings.flags("refresh-only"));
+    let _lock = lock_project(&app, &window, &project_path, "plan")?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    let result = runner::run_terraform_json_plan(
+        &app,
+        window.label(),
+        &terraform_dir,
+        &ctx,
+        DRIFT_PLAN_FILE,
+        &as_strs(&args),
+    )
+    .await?;
+    let _ = tokio::fs::remove_file(terraform_dir.join(DRIFT_PLAN_FILE)).await;
+    integrity::forget(Path::new(&project_path), DRIFT_PLAN_FILE).await;
+
+    let drifted = result
+        .plan
+        .as_ref()
+        .map(drift::drifted_resources)
+        .unwrap_or_default();
+
+    Ok(TerraformDriftResult {
+        success: result.success,
+        code: result.code,
+        diagnostics: result.diagnostics,
+        drifted,
+    })
+}
+
+/// Apply a previously saved plan file (`tfplan`) in the workspace it was created in.
+/// A `workspace` other than that one is rejected.
+/// The plan is refused, with `stale_plan` set on the result, when its file, the
+/// configuration files or the git HEAD changed since it was created, and when it
+/// violates a blocking rule of the project policy.
+#[command]
+pub async fn terraform_apply_plan(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    workspace: Option<String>,
+) -> Result<TerraformJsonResult, String> {
+    let project_dir = Path::new(&project_path);
+    let planned_workspace = integrity::planned_workspace(project_dir, PLAN_FILE).await;
+    if let (Some(workspace), Some(planned)) = (&workspace, &planned_workspace) {
+        if workspace != planned {
+            return Err(format!(
+                "The saved plan was created for workspace {}, not {}",
+                planned, workspace
+            ));
+        }
+    }
+    // A saved plan already carries its variable values, so no secrets are needed here
+    let ctx = workspace_context(&project_path, planned_workspace.or(workspace)).await?;
+    let _lock = lock_project(&app, &window, &project_path, "apply")?;
+    let terraform_dir = ctx.settings.terraform_dir(project_dir);
+    if let Some(stale) = integrity::check(project_dir, &terraform_dir, PLAN_FILE).await? {
+        return Ok(integrity::stale_result(stale));
+    }
+    let plan_file = terraform_dir.join(PLAN_FILE);
+    apply_checked_plan(&app, &window, project_dir, &terraform_dir, &ctx, &plan_file).await
+}
+
+/// Apply a saved plan unless it violates a blocking policy rule. Non-blocking
+/// violations are reported alongside terraform's own diagnostics.
+async fn apply_checked_plan(
+    app: &AppHandle,
+    window: &WebviewWindow,
+    project_dir: &Path,
+    terraform_dir: &Path,
+    ctx: &RunContext,
+    plan_file: &Path,
+) -> Result<TerraformJsonResult, String> {
+    let violations = policy::check_plan(project_dir, terraform_dir, ctx, plan_file).await?;
+    if policy::is_blocking(&violations) {
+        return Ok(policy::blocked_result(violations));
+    }
+    let mut args = ctx.settings.flags("apply-plan");
+    args.push(plan_file.to_string_lossy().to_string());
+    let mut result = runner::run_terraform_json(app, window.label(), terraform_dir, ctx, "apply", &as_strs(&args)).await?;
+    result.diagnostics.splice(0..0, violations);
+    Ok(result)
+}
+
+/// Check a saved plan against the project policy (`.terrastudio/policy.json`)
+/// without applying it. Checks `tfplan`, or the archived plan `plan_id`.
+#[command]
+pub async fn terraform_policy_check(
+    project_path: String,
+    plan_id: Option<String>,
+    workspace: Option<String>,
+) -> Result<Vec<TerraformDiagnostic>, String> {
+    let project_dir = Path::new(&project_path);
+    let ctx = workspace_context(&project_path, workspace).await?;
+    let terraform_dir = ctx.settings.terraform_dir(project_dir);
+    let plan_file = match &plan_id {
+        Some(id) => archive::plan_path(id)?,
+        None => terraform_dir.join(PLAN_FILE),
+    };
+    if !plan_file.exists() {
+        return Err("No saved plan found; run plan first".to_string());
+    }
+    policy::check_plan(project_dir, &terraform_dir, &ctx, &plan_file).await
+}
+
+/// Apply a plan from the plan archive, exactly as it was archived.
+/// The plan runs in the workspace it was created in; a different `workspace`
+/// is rejected. Stale plans are refused as for `terraform_apply_plan`.
 #[command]
-pub async fn terraform_plan_with_out(app: AppHandle, window: WebviewWindow, project_path: String) -> Result<TerraformPlanResult, String> {
-    let terraform_dir = PathBuf::from(&project_path).join("terraform");
-    runner::run_terraform_json_plan(&app, window.label(), &terraform_dir).await
+pub async fn terraform_apply_archived_plan(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    plan_id: String,
+    workspace: Option<String>,
+) -> Result<TerraformJsonResult, String> {
+    let project_dir = Path::new(&project_path);
+    let meta = archive::read_meta(project_dir, &plan_id).await?;
+    let Some(planned_workspace) = meta.workspace else {
+        return Err(format!("Plan {} does not record its workspace; run plan again", plan_id));
+    };
+    if let Some(workspace) = workspace.filter(|w| *w != planned_workspace) {
+        return Err(format!(
+            "Plan {} was created for workspace {}, not {}",
+            plan_id, planned_workspace, workspace
+        ));
+    }
+    let ctx = workspace_context(&project_path, Some(planned_workspace)).await?;
+    let _lock = lock_project(&app, &window, &project_path, "apply")?;
+    let terraform_dir = ctx.settings.terraform_dir(project_dir);
+    if let Some(stale) = archive::check(project_dir, &terraform_dir, &plan_id).await? {
+        return Ok(integrity::stale_result(stale));
+    }
+    let plan_file = archive::plan_path(&plan_id)?;
+    apply_checked_plan(&app, &window, project_dir, &terraform_dir, &ctx, &plan_file).await
+}
+
+/// Run terraform show -json and return the current state, parsed.
+/// `workspace` selects whose state is shown; defaults to the selected workspace.
+#[command]
+pub async fn terraform_show(project_path: String, workspace: Option<String>) -> Result<TerraformState, String> {
+    let ctx = workspace_context(&project_path, workspace).await?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    state::show_state(&terraform_dir, &ctx).await
+}
+
+/// Look up deployed resources in the current state, keyed by address.
+/// Without `addresses`, every managed resource is returned. Addresses not in
+/// state are omitted from the result.
+#[command]
+pub async fn terraform_show_resources(
+    project_path: String,
+    addresses: Option<Vec<String>>,
+    workspace: Option<String>,
+) -> Result<HashMap<String, StateResource>, String> {
+    let ctx = workspace_context(&project_path, workspace).await?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    let state = state::show_state(&terraform_dir, &ctx).await?;
+    let resources = match addresses {
+        Some(addresses) => {
+            let by_address = state.resources_by_address();
+            addresses
+                .iter()
+                .filter_map(|a| by_address.get(a.as_str()).map(|r| (a.clone(), (*r).clone())))
+                .collect()
+        }
+        None => state
+            .managed_resources()
+            .into_iter()
+            .map(|r| (r.address.clone(), r.clone()))
+            .collect(),
+    };
+    Ok(resources)
 }
 
-/// Apply a previously saved plan file (`tfplan`).
+/// Run terraform state list and return every resource address in the state.
 #[command]
-pub async fn terraform_apply_plan(app: AppHandle, window: WebviewWindow, project_path: String) -> Result<TerraformJsonResult, String> {
-    let terraform_dir = PathBuf::from(&project_path).join("terraform");
-    let plan_file = terraform_dir.join("tfplan");
-    let plan_path = plan_file.to_string_lossy().to_string();
-    runner::run_terraform_json(&app, window.label(), &terraform_dir, "apply", &[&plan_path]).await
+pub async fn terraform_state_list(project_path: String, workspace: Option<String>) -> Result<Vec<String>, String> {
+    let ctx = workspace_context(&project_path, workspace).await?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    let output = runner::run_terraform_capture(&terraform_dir, &ctx, "state", &["list", "-no-color"]).await?;
+    Ok(output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Show a single resource from the state.
+/// `terraform state show` has no JSON mode, so this reads `show -json` instead.
+#[command]
+pub async fn terraform_state_show(
+    project_path: String,
+    address: String,
+    workspace: Option<String>,
+) -> Result<StateResource, String> {
+    sanitize_address(&address)?;
+    let ctx = workspace_context(&project_path, workspace).await?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    let state = state::show_state(&terraform_dir, &ctx).await?;
+    let resource = state
+        .resources_by_address()
+        .get(address.as_str())
+        .map(|r| (*r).clone());
+    resource.ok_or_else(|| format!("Resource {} is not in the state", address))
+}
+
+/// Run terraform state mv to move a resource or module to a new address,
+/// e.g. after a node is renamed, so terraform does not destroy and recreate it.
+#[command]
+pub async fn terraform_state_mv(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    source: String,
+    destination: String,
+    workspace: Option<String>,
+) -> Result<bool, String> {
+    sanitize_address(&source)?;
+    sanitize_address(&destination)?;
+    if source == destination {
+        return Err("Source and destination addresses are the same".to_string());
+    }
+    let ctx = workspace_context(&project_path, workspace).await?;
+    let _lock = lock_project(&app, &window, &project_path, "state mv")?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    let flags = ctx.settings.flags("state");
+    let mut args = vec!["mv", "-no-color"];
+    args.extend(as_strs(&flags));
+    args.extend([source.as_str(), destination.as_str()]);
+    let result = runner::run_terraform(&app, window.label(), &terraform_dir, &ctx, "state", &args).await?;
+    Ok(result.success)
+}
+
+/// Run terraform state rm to stop managing resources without destroying them.
+#[command]
+pub async fn terraform_state_rm(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    addresses: Vec<String>,
+    workspace: Option<String>,
+) -> Result<bool, String> {
+    if addresses.is_empty() {
+        return Err("No addresses given to remove from the state".to_string());
+    }
+    for address in &addresses {
+        sanitize_address(address)?;
+    }
+    let ctx = workspace_context(&project_path, workspace).await?;
+    let _lock = lock_project(&app, &window, &project_path, "state rm")?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    let flags = ctx.settings.flags("state");
+    let mut args = vec!["rm", "-no-color"];
+    args.extend(as_strs(&flags));
+    args.extend(addresses.iter().map(String::as_str));
+    let result = runner::run_terraform(&app, window.label(), &terraform_dir, &ctx, "state", &args).await?;
+    Ok(result.success)
+}
+
+/// Convert deployed infrastructure into diagram JSON to bootstrap a project.
+/// Reads `state_file` (a `.tfstate` file or saved `terraform show -json` output)
+/// when given, otherwise the current state of `project_path`.
+/// `types` maps terraform resource types to hints from the plugin registry.
+#[command]
+pub async fn terraform_state_to_diagram(
+    project_path: Option<String>,
+    state_file: Option<String>,
+    workspace: Option<String>,
+    types: HashMap<String, DiagramTypeHint>,
+) -> Result<DiagramImport, String> {
+    let state = match (state_file, project_path) {
+        (Some(state_file), _) => {
+            let json = tokio::fs::read_to_string(&state_file)
+                .await
+                .map_err(|e| format!("Failed to read state file: {}", e))?;
+            TerraformState::parse(&json)?
+        }
+        (None, Some(project_path)) => {
+            let ctx = workspace_context(&project_path, workspace).await?;
+            let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+            state::show_state(&terraform_dir, &ctx).await?
+        }
+        (None, None) => return Err("Either a project or a state file is required".to_string()),
+    };
+    Ok(diagram::state_to_diagram(&state, &types))
+}
+
+/// Report which window, if any, is currently running terraform against a project.
+#[command]
+pub fn terraform_lock_holder(app: AppHandle, project_path: String) -> Option<LockHolder> {
+    app.state::<ProjectLocks>().holder(&project_path)
+}
+
+/// Cancel a running terraform command owned by the calling window.
+/// Without a `run_id`, every terraform run in the window is cancelled.
+/// Returns the number of runs that were signalled.
+#[command]
+pub fn terraform_cancel(app: AppHandle, window: WebviewWindow, run_id: Option<String>) -> Result<usize, String> {
+    let registry = app.state::<ProcessRegistry>();
+    match run_id {
+        Some(id) => {
+            if registry.cancel(window.label(), &id) {
+                Ok(1)
+            } else {
+                Err(format!("No running terraform command with ID {}", id))
+            }
+        }
+        None => Ok(registry.cancel_window(window.label(), Some(ProcessKind::Terraform))),
+    }
 }
 
-/// Run terraform show -json to get the current state as JSON.
+/// List the terraform and git processes currently running for the calling window.
 #[command]
-pub async fn terraform_show(project_path: String) -> Result<String, String> {
-    let terraform_dir = PathBuf::from(&project_path).join("terraform");
-    runner::run_terraform_capture(&terraform_dir, "show", &["-json"]).await
+pub fn terraform_running(app: AppHandle, window: WebviewWindow) -> Vec<RunningProcess> {
+    app.state::<ProcessRegistry>().list(window.label())
 }
 
 /// Read a generated terraform file's content.
@@ -116,7 +1050,7 @@ pub async fn read_terraform_file(
 ) -> Result<String, String> {
     let safe_path = security::sanitize_filepath(&filename)
         .map_err(|e| format!("Invalid file path: {}", e))?;
-    let file_path = PathBuf::from(&project_path).join("terraform").join(safe_path);
+    let file_path = settings::terraform_dir(Path::new(&project_path)).await?.join(safe_path);
     tokio::fs::read_to_string(&file_path)
         .await
         .map_err(|e| format!("Failed to read {}: {}", filename, e))
@@ -126,7 +1060,7 @@ pub async fn read_terraform_file(
 /// Returns relative paths (e.g., "main.tf", "modules/net/main.tf").
 #[command]
 pub async fn list_terraform_files(project_path: String) -> Result<Vec<String>, String> {
-    let terraform_dir = PathBuf::from(&project_path).join("terraform");
+    let terraform_dir = settings::terraform_dir(Path::new(&project_path)).await?;
     if !terraform_dir.exists() {
         return Ok(vec![]);
     }
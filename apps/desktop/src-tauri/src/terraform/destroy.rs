@@ -0,0 +1,90 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::lock::lock_key;
+use super::runner::TerraformDiagnostic;
+
+/// Saved destroy plan, relative to the terraform dir. Kept under `.terraform/`
+/// so it is never mistaken for the reviewable `tfplan`.
+pub const DESTROY_PLAN_FILE: &str = ".terraform/terrastudio-destroy.tfplan";
+
+/// How long a confirmation token stays valid after the destroy plan.
+pub const TOKEN_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Result of `terraform_destroy_plan`: what would be deleted, and the token
+/// `terraform_destroy_apply` needs to go ahead.
+#[derive(Clone, Serialize)]
+pub struct DestroyPlanResult {
+    pub success: bool,
+    pub code: i32,
+    pub diagnostics: Vec<TerraformDiagnostic>,
+    /// Addresses of the resources the destroy would delete.
+    pub resources: Vec<String>,
+    /// One-time confirmation token; `None` when the plan failed.
+    pub token: Option<String>,
+    /// Name the user must type back to confirm.
+    pub project_name: String,
+    pub expires_in_secs: u64,
+}
+
+struct PendingDestroy {
+    token: String,
+    workspace: Option<String>,
+    issued_at: Instant,
+}
+
+/// Managed state holding the outstanding destroy confirmation per project.
+/// Planning again replaces the previous token; redeeming a token consumes it.
+#[derive(Default)]
+pub struct DestroyTokens {
+    pending: Mutex<HashMap<PathBuf, PendingDestroy>>,
+}
+
+impl DestroyTokens {
+    /// Issue a token for the destroy plan just saved for `project_path`.
+    pub fn issue(&self, project_path: &str, workspace: Option<String>) -> String {
+        let token = uuid::Uuid::new_v4().to_string();
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(
+                lock_key(Path::new(project_path)),
+                PendingDestroy {
+                    token: token.clone(),
+                    workspace,
+                    issued_at: Instant::now(),
+                },
+            );
+        }
+        token
+    }
+
+    /// Consume the token for `project_path` and return the workspace the destroy
+    /// was planned in. Fails when there is no token, it does not match, or it expired.
+    pub fn redeem(&self, project_path: &str, token: &str) -> Result<Option<String>, String> {
+        let mut pending = self
+            .pending
+            .lock()
+            .map_err(|_| "Destroy confirmations are unavailable".to_string())?;
+        let key = lock_key(Path::new(project_path));
+        match pending.remove(&key) {
+            None => Err("No destroy is pending for this project; run the destroy plan first".to_string()),
+            Some(p) if p.token != token => {
+                pending.insert(key, p);
+                Err("Confirmation token does not match the latest destroy plan".to_string())
+            }
+            Some(p) if p.issued_at.elapsed() > TOKEN_TTL => {
+                Err("Confirmation token expired; run the destroy plan again".to_string())
+            }
+            Some(p) => Ok(p.workspace),
+        }
+    }
+
+    /// Drop the outstanding token for `project_path`.
+    pub fn revoke(&self, project_path: &str) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(&lock_key(Path::new(project_path)));
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::state::{StateResource, TerraformState};
+
+/// Container resources in order of nesting depth. A resource is placed inside
+/// the deepest container it references.
+const CONTAINER_TYPES: &[&str] = &["azurerm_resource_group", "azurerm_virtual_network", "azurerm_subnet"];
+
+const LEAF_WIDTH: f64 = 220.0;
+const LEAF_HEIGHT: f64 = 100.0;
+const PADDING: f64 = 30.0;
+const HEADER: f64 = 60.0;
+
+/// What the frontend plugin registry knows about one terraform resource type.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagramTypeHint {
+    /// Diagram type ID, e.g. "azurerm/networking/virtual_network".
+    pub type_id: String,
+    /// Schema property keys to copy from state attributes of the same name.
+    /// Sensitive properties should be left out; sensitive attributes are skipped regardless.
+    #[serde(default)]
+    pub properties: Vec<String>,
+    #[serde(default)]
+    pub references: Vec<ReferenceHint>,
+    /// Container type IDs this resource may be placed inside. Empty means any.
+    #[serde(default)]
+    pub can_be_child_of: Vec<String>,
+}
+
+/// A `reference` property of a resource schema.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceHint {
+    pub key: String,
+    #[serde(default)]
+    pub target_types: Vec<String>,
+}
+
+/// Result of converting state into a diagram.
+#[derive(Clone, Serialize)]
+pub struct DiagramImport {
+    /// `{ nodes, edges }` in the shape stored in `diagrams/main.json`.
+    pub diagram: Value,
+    /// Managed resources with no matching diagram type, by address.
+    pub unmapped: Vec<String>,
+    /// State address to diagram address, for mapped resources whose address changes.
+    pub renamed: BTreeMap<String, String>,
+}
+
+struct DraftNode<'a> {
+    id: String,
+    resource: &'a StateResource,
+    hint: &'a DiagramTypeHint,
+    terraform_name: String,
+    parent: Option<usize>,
+    references: Map<String, Value>,
+}
+
+/// Build a diagram with one node per managed resource that has a type hint.
+pub fn state_to_diagram(state: &TerraformState, hints: &HashMap<String, DiagramTypeHint>) -> DiagramImport {
+    let mut unmapped = Vec::new();
+    let mut renamed = BTreeMap::new();
+    let mut nodes: Vec<DraftNode> = Vec::new();
+    let mut used_names: HashSet<(String, String)> = HashSet::new();
+
+    for resource in state.managed_resources() {
+        if resource.deposed_key.is_some() {
+            continue;
+        }
+        let Some(hint) = hints.get(&resource.resource_type) else {
+            unmapped.push(resource.address.clone());
+            continue;
+        };
+        let terraform_name = unique_name(&hint.type_id, &base_name(resource), &mut used_names);
+        let address = format!("{}.{}", resource.resource_type, terraform_name);
+        if address != resource.address {
+            renamed.insert(resource.address.clone(), address);
+        }
+        nodes.push(DraftNode {
+            id: format!("{}-{}", hint.type_id.replace('/', "-"), terraform_name),
+            resource,
+            hint,
+            terraform_name,
+            parent: None,
+            references: Map::new(),
+        });
+    }
+
+    // Azure IDs are case-insensitive
+    let by_id: HashMap<String, usize> = nodes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, n)| attr_str(n.resource, "id").map(|id| (id.to_lowercase(), i)))
+        .collect();
+
+    for i in 0..nodes.len() {
+        let mut referenced: Vec<(String, usize)> = Vec::new();
+        if let Some(values) = nodes[i].resource.values.as_object() {
+            for (key, value) in values {
+                if key == "id" {
+                    continue;
+                }
+                collect_id_refs(key, value, &by_id, &mut referenced);
+            }
+        }
+        referenced.retain(|(_, target)| *target != i);
+
+        let parent = choose_parent(&nodes, i, &referenced);
+        let references = infer_references(&nodes, i, &referenced);
+        nodes[i].parent = parent;
+        nodes[i].references = references;
+    }
+
+    DiagramImport {
+        diagram: json!({ "nodes": layout(&nodes), "edges": [] }),
+        unmapped,
+        renamed,
+    }
+}
+
+/// Gather `(attribute, node)` pairs for every string, at any depth under a
+/// top-level attribute, that is the ID of another resource.
+fn collect_id_refs(key: &str, value: &Value, by_id: &HashMap<String, usize>, out: &mut Vec<(String, usize)>) {
+    match value {
+        Value::String(s) => {
+            if let Some(&target) = by_id.get(&s.to_lowercase()) {
+                if !out.iter().any(|(k, t)| k == key && *t == target) {
+                    out.push((key.to_string(), target));
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_id_refs(key, v, by_id, out)),
+        Value::Object(map) => map.values().for_each(|v| collect_id_refs(key, v, by_id, out)),
+        _ => {}
+    }
+}
+
+fn container_rank(resource: &StateResource) -> Option<usize> {
+    CONTAINER_TYPES.iter().position(|t| *t == resource.resource_type)
+}
+
+/// Pick the deepest container the resource belongs to, by ID reference or by the
+/// `resource_group_name` / `virtual_network_name` attributes.
+fn choose_parent(nodes: &[DraftNode], i: usize, referenced: &[(String, usize)]) -> Option<usize> {
+    let node = &nodes[i];
+    let own_rank = container_rank(node.resource);
+    let rg_name = attr_str(node.resource, "resource_group_name").map(str::to_lowercase);
+    let vnet_name = attr_str(node.resource, "virtual_network_name").map(str::to_lowercase);
+
+    let mut candidates: Vec<usize> = referenced.iter().map(|(_, t)| *t).collect();
+    for (j, other) in nodes.iter().enumerate() {
+        let name = attr_str(other.resource, "name").map(str::to_lowercase);
+        let matches = match other.resource.resource_type.as_str() {
+            "azurerm_resource_group" => name.is_some() && name == rg_name,
+            "azurerm_virtual_network" => {
+                name.is_some()
+                    && name == vnet_name
+                    && attr_str(other.resource, "resource_group_name").map(str::to_lowercase) == rg_name
+            }
+            _ => false,
+        };
+        if matches && j != i {
+            candidates.push(j);
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|j| container_rank(nodes[j].resource).map(|rank| (rank, j)))
+        // Containers only nest inside shallower containers
+        .filter(|(rank, _)| own_rank.map_or(true, |own| *rank < own))
+        .filter(|(_, j)| {
+            node.hint.can_be_child_of.is_empty() || node.hint.can_be_child_of.contains(&nodes[*j].hint.type_id)
+        })
+        .max_by_key(|(rank, j)| (*rank, std::cmp::Reverse(*j)))
+        .map(|(_, j)| j)
+}
+
+/// Map ID references onto the schema's reference properties: first by matching
+/// attribute name, then by the first free property that accepts the target type.
+fn infer_references(nodes: &[DraftNode], i: usize, referenced: &[(String, usize)]) -> Map<String, Value> {
+    let hint = nodes[i].hint;
+    let mut references = Map::new();
+    for (attr, target) in referenced {
+        let target_type = &nodes[*target].hint.type_id;
+        let accepts = |r: &&ReferenceHint| r.target_types.is_empty() || r.target_types.contains(target_type);
+        let key = hint
+            .references
+            .iter()
+            .filter(accepts)
+            .find(|r| &r.key == attr)
+            .or_else(|| {
+                hint.references
+                    .iter()
+                    .filter(accepts)
+                    .find(|r| !r.target_types.is_empty() && !references.contains_key(&r.key))
+            });
+        if let Some(reference) = key {
+            references
+                .entry(reference.key.clone())
+                .or_insert_with(|| Value::String(nodes[*target].id.clone()));
+        }
+    }
+    references
+}
+
+fn attr_str<'a>(resource: &'a StateResource, key: &str) -> Option<&'a str> {
+    resource.values.get(key).and_then(Value::as_str)
+}
+
+fn is_sensitive(resource: &StateResource, key: &str) -> bool {
+    resource.sensitive_values.get(key).is_some_and(|v| v != &Value::Bool(false) && !v.is_null())
+}
+
+/// Resource name plus instance key, e.g. `web_0` or `web_eastus`.
+fn base_name(resource: &StateResource) -> String {
+    let suffix = match &resource.index {
+        Some(Value::Number(n)) => format!("_{}", n),
+        Some(Value::String(s)) => format!("_{}", s),
+        _ => String::new(),
+    };
+    let raw = format!("{}{}", resource.name, suffix).to_lowercase();
+    raw.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Terraform names must be unique per type once module prefixes are dropped.
+fn unique_name(type_id: &str, base: &str, used: &mut HashSet<(String, String)>) -> String {
+    let mut name = base.to_string();
+    let mut counter = 2;
+    while used.contains(&(type_id.to_string(), name.clone())) {
+        name = format!("{}_{}", base, counter);
+        counter += 1;
+    }
+    used.insert((type_id.to_string(), name.clone()));
+    name
+}
+
+fn node_properties(node: &DraftNode) -> Map<String, Value> {
+    let mut properties = Map::new();
+    for key in &node.hint.properties {
+        if is_sensitive(node.resource, key) {
+            continue;
+        }
+        match node.resource.values.get(key) {
+            None | Some(Value::Null) => {}
+            Some(value) => {
+                properties.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    properties
+}
+
+/// Lay nodes out on a simple grid, children inside their containers.
+/// Parents are emitted before their children, as the canvas requires.
+fn layout(nodes: &[DraftNode]) -> Vec<Value> {
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    let mut roots = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        match node.parent {
+            Some(p) => children[p].push(i),
+            None => roots.push(i),
+        }
+    }
+
+    let mut sizes: Vec<(f64, f64)> = nodes
+        .iter()
+        .map(|n| match container_rank(n.resource) {
+            Some(_) => (LEAF_WIDTH + 2.0 * PADDING, LEAF_HEIGHT + HEADER + PADDING),
+            None => (LEAF_WIDTH, LEAF_HEIGHT),
+        })
+        .collect();
+    for &root in &roots {
+        measure(root, &children, &mut sizes);
+    }
+
+    let mut out = Vec::new();
+    place(&roots, None, &children, &sizes, nodes, &mut out);
+    out
+}
+
+fn grid_columns(count: usize) -> usize {
+    (count as f64).sqrt().ceil().max(1.0) as usize
+}
+
+fn measure(i: usize, children: &[Vec<usize>], sizes: &mut [(f64, f64)]) -> (f64, f64) {
+    if children[i].is_empty() {
+        return sizes[i];
+    }
+    let child_sizes: Vec<(f64, f64)> = children[i].iter().map(|&c| measure(c, children, sizes)).collect();
+    let columns = grid_columns(child_sizes.len());
+    let width: f64 = child_sizes
+        .chunks(columns)
+        .map(|row| row.iter().map(|s| s.0 + PADDING).sum::<f64>())
+        .fold(0.0, f64::max);
+    let height: f64 = child_sizes
+        .chunks(columns)
+        .map(|row| row.iter().map(|s| s.1).fold(0.0, f64::max) + PADDING)
+        .sum();
+    sizes[i] = (width + PADDING, height + HEADER);
+    sizes[i]
+}
+
+fn place(
+    members: &[usize],
+    parent: Option<usize>,
+    children: &[Vec<usize>],
+    sizes: &[(f64, f64)],
+    nodes: &[DraftNode],
+    out: &mut Vec<Value>,
+) {
+    let columns = grid_columns(members.len());
+    let (origin_x, origin_y) = if parent.is_some() { (PADDING, HEADER) } else { (0.0, 0.0) };
+    let mut y = origin_y;
+    for row in members.chunks(columns) {
+        let mut x = origin_x;
+        for &i in row {
+            let container = container_rank(nodes[i].resource).is_some() || !children[i].is_empty();
+            out.push(node_json(&nodes[i], parent.map(|p| nodes[p].id.as_str()), (x, y), sizes[i], container));
+            place(&children[i], Some(i), children, sizes, nodes, out);
+            x += sizes[i].0 + PADDING;
+        }
+        y += row.iter().map(|&i| sizes[i].1).fold(0.0, f64::max) + PADDING;
+    }
+}
+
+fn node_json(node: &DraftNode, parent_id: Option<&str>, position: (f64, f64), size: (f64, f64), container: bool) -> Value {
+    let label = attr_str(node.resource, "name").unwrap_or(&node.resource.name);
+    let mut value = json!({
+        "id": node.id,
+        "type": node.hint.type_id,
+        "position": { "x": position.0, "y": position.1 },
+        "data": {
+            "typeId": node.hint.type_id,
+            "properties": node_properties(node),
+            "references": node.references,
+            "terraformName": node.terraform_name,
+            "label": label,
+            "validationErrors": [],
+            "enabledOutputs": [],
+        },
+    });
+    let obj = value.as_object_mut().expect("node is an object");
+    if let Some(parent_id) = parent_id {
+        obj.insert("parentId".into(), Value::String(parent_id.to_string()));
+    }
+    if container {
+        obj.insert("width".into(), json!(size.0));
+        obj.insert("height".into(), json!(size.1));
+        obj.insert("style".into(), json!(format!("width: {}px; height: {}px;", size.0, size.1)));
+    }
+    value
+}
@@ -0,0 +1,147 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use super::plan::{ResourceChange, TerraformPlan};
+use super::runner::TerraformDiagnostic;
+
+/// A resource whose real infrastructure no longer matches the state.
+#[derive(Clone, Serialize)]
+pub struct DriftedResource {
+    pub address: String,
+    pub resource_type: String,
+    pub name: String,
+    /// "update" when attributes changed out-of-band, "delete" when the object is gone.
+    pub action: String,
+    pub attributes: Vec<AttributeDrift>,
+}
+
+/// A single attribute that changed outside of terraform.
+/// Sensitive attributes are reported without their values.
+#[derive(Clone, Serialize)]
+pub struct AttributeDrift {
+    /// Dotted attribute path, e.g. "tags.env" or "ip_rules[0]".
+    pub path: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub sensitive: bool,
+}
+
+/// Result of a refresh-only plan.
+#[derive(Clone, Serialize)]
+pub struct TerraformDriftResult {
+    pub success: bool,
+    pub code: i32,
+    pub diagnostics: Vec<TerraformDiagnostic>,
+    pub drifted: Vec<DriftedResource>,
+}
+
+/// Extract the drifted resources from a plan's `resource_drift` section.
+pub fn drifted_resources(plan: &TerraformPlan) -> Vec<DriftedResource> {
+    plan.resource_drift
+        .iter()
+        .filter(|rc| rc.mode != "data")
+        .filter_map(drifted_resource)
+        .collect()
+}
+
+fn drifted_resource(rc: &ResourceChange) -> Option<DriftedResource> {
+    let action = if rc.change.actions.iter().any(|a| a == "delete") {
+        "delete"
+    } else if rc.change.actions.iter().any(|a| a == "update") {
+        "update"
+    } else {
+        return None;
+    };
+
+    let mut attributes = Vec::new();
+    if action == "update" {
+        let null = Value::Null;
+        diff_values(
+            String::new(),
+            rc.change.before.as_ref().unwrap_or(&null),
+            rc.change.after.as_ref().unwrap_or(&null),
+            rc.change.before_sensitive.as_ref(),
+            rc.change.after_sensitive.as_ref(),
+            &mut attributes,
+        );
+    }
+
+    Some(DriftedResource {
+        address: rc.address.clone(),
+        resource_type: rc.resource_type.clone(),
+        name: rc.name.clone(),
+        action: action.to_string(),
+        attributes,
+    })
+}
+
+/// Recursively compare two attribute values, recording every leaf that differs.
+/// Objects are walked key by key; arrays of equal length element by element.
+fn diff_values(
+    path: String,
+    before: &Value,
+    after: &Value,
+    before_sensitive: Option<&Value>,
+    after_sensitive: Option<&Value>,
+    out: &mut Vec<AttributeDrift>,
+) {
+    if before == after {
+        return;
+    }
+
+    let sensitive = is_sensitive(before_sensitive) || is_sensitive(after_sensitive);
+    if !sensitive {
+        match (before, after) {
+            (Value::Object(b), Value::Object(a)) => {
+                let mut keys: Vec<&String> = b.keys().chain(a.keys()).collect();
+                keys.sort();
+                keys.dedup();
+                for key in keys {
+                    diff_values(
+                        join_key(&path, key),
+                        b.get(key).unwrap_or(&Value::Null),
+                        a.get(key).unwrap_or(&Value::Null),
+                        before_sensitive.and_then(|s| s.get(key)),
+                        after_sensitive.and_then(|s| s.get(key)),
+                        out,
+                    );
+                }
+                return;
+            }
+            (Value::Array(b), Value::Array(a)) if b.len() == a.len() => {
+                for (i, (bv, av)) in b.iter().zip(a.iter()).enumerate() {
+                    diff_values(
+                        format!("{}[{}]", path, i),
+                        bv,
+                        av,
+                        before_sensitive.and_then(|s| s.get(i)),
+                        after_sensitive.and_then(|s| s.get(i)),
+                        out,
+                    );
+                }
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    out.push(AttributeDrift {
+        path,
+        before: if sensitive { None } else { Some(before.clone()) },
+        after: if sensitive { None } else { Some(after.clone()) },
+        sensitive,
+    });
+}
+
+/// Sensitivity masks mirror the value shape, with `true` marking sensitive leaves.
+fn is_sensitive(mask: Option<&Value>) -> bool {
+    matches!(mask, Some(Value::Bool(true)))
+}
+
+fn join_key(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command;
+
+use super::settings::RunSettings;
+use super::versions;
+use crate::project::commands::read_project_config;
+
+/// Infrastructure-as-code CLI a project runs with, from `projectConfig.engine.type`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineKind {
+    #[default]
+    Terraform,
+    #[serde(rename = "opentofu")]
+    OpenTofu,
+}
+
+impl EngineKind {
+    pub const ALL: [EngineKind; 2] = [EngineKind::Terraform, EngineKind::OpenTofu];
+
+    /// Binary looked up on PATH when no explicit path is configured.
+    pub fn default_binary(self) -> &'static str {
+        match self {
+            EngineKind::Terraform => "terraform",
+            EngineKind::OpenTofu => "tofu",
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            EngineKind::Terraform => "Terraform",
+            EngineKind::OpenTofu => "OpenTofu",
+        }
+    }
+}
+
+/// `projectConfig.engine`, e.g. `{ "type": "opentofu", "path": "/opt/tofu/bin/tofu" }`.
+#[derive(Debug, Default, Deserialize)]
+struct EngineConfig {
+    #[serde(rename = "type", default)]
+    kind: EngineKind,
+    #[serde(default)]
+    path: Option<String>,
+    /// Pick a terraform binary from the managed cache matching `required_version`.
+    #[serde(default)]
+    managed: bool,
+}
+
+/// The CLI binary every terraform run for a project goes through.
+#[derive(Clone, Debug)]
+pub struct Engine {
+    pub kind: EngineKind,
+    pub binary: String,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::on_path(EngineKind::default())
+    }
+}
+
+impl Engine {
+    pub fn on_path(kind: EngineKind) -> Self {
+        Engine {
+            kind,
+            binary: kind.default_binary().to_string(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.kind.display_name()
+    }
+
+    /// A new process for this engine's binary. The caller adds the subcommand.
+    pub fn command(&self) -> Command {
+        #[allow(unused_mut)]
+        let mut cmd = Command::new(&self.binary);
+        // On Windows, prevent a console window from flashing
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        cmd
+    }
+
+    /// Error message for a binary that could not be started.
+    pub fn spawn_error(&self, e: std::io::Error) -> String {
+        format!(
+            "Failed to spawn {}: {}. Is {} installed and on your PATH?",
+            self.binary,
+            e,
+            self.name()
+        )
+    }
+}
+
+/// Validate a configured engine binary path. The project file may come from a
+/// shared repository, so the path must be absolute and name the engine's own
+/// binary (`terraform`/`tofu`, optionally `.exe`) rather than an arbitrary program.
+fn sanitize_binary_path(kind: EngineKind, path: &str) -> Result<String, String> {
+    if path.contains('\0') {
+        return Err("Engine path contains null byte".to_string());
+    }
+    let p = Path::new(path);
+    if !p.is_absolute() {
+        return Err(format!("Engine path must be absolute: {}", path));
+    }
+    let file_name = p.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let expected = kind.default_binary();
+    if file_name != expected && file_name != format!("{}.exe", expected) {
+        return Err(format!(
+            "Engine path for {} must point to a '{}' binary: {}",
+            kind.display_name(),
+            expected,
+            path
+        ));
+    }
+    Ok(path.to_string())
+}
+
+/// Read the project's engine from `projectConfig.engine`. Projects without one
+/// use terraform from PATH.
+pub async fn read_engine(project_dir: &Path) -> Result<Engine, String> {
+    let project_config = read_project_config(project_dir).await?;
+    let terraform_dir = RunSettings::from_config(&project_config)?.terraform_dir(project_dir);
+    engine_from_config(&project_config, &terraform_dir).await
+}
+
+/// Resolve the engine from a parsed `projectConfig`. `terraform_dir` is read for
+/// `required_version` when the project uses managed versions.
+pub async fn engine_from_config(project_config: &serde_json::Value, terraform_dir: &Path) -> Result<Engine, String> {
+    let config = match project_config.get("engine") {
+        None | Some(serde_json::Value::Null) => EngineConfig::default(),
+        Some(value) => serde_json::from_value::<EngineConfig>(value.clone())
+            .map_err(|e| format!("Invalid engine configuration: {}", e))?,
+    };
+    if config.managed {
+        if config.kind != EngineKind::Terraform {
+            return Err(format!("Managed versions are not supported for {}", config.kind.display_name()));
+        }
+        if config.path.is_some() {
+            return Err("Engine path and managed versions cannot be combined".to_string());
+        }
+        let resolved = versions::resolve(terraform_dir).await?;
+        return Ok(Engine {
+            kind: config.kind,
+            binary: resolved.path,
+        });
+    }
+    match config.path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        Some(path) => Ok(Engine {
+            kind: config.kind,
+            binary: sanitize_binary_path(config.kind, path)?,
+        }),
+        None => Ok(Engine::on_path(config.kind)),
+    }
+}
+
+/// Features that depend on the engine and its version.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct EngineCapabilities {
+    /// `import` blocks and `plan -generate-config-out`.
+    pub import_blocks: bool,
+    /// `removed` blocks for forgetting resources without destroying them.
+    pub removed_blocks: bool,
+    /// Client-side state and plan encryption (OpenTofu only).
+    pub state_encryption: bool,
+}
+
+impl EngineCapabilities {
+    fn for_version(kind: EngineKind, version: (u64, u64, u64)) -> Self {
+        let at_least = |major, minor| (version.0, version.1) >= (major, minor);
+        EngineCapabilities {
+            import_blocks: at_least(1, 5),
+            removed_blocks: at_least(1, 7),
+            state_encryption: kind == EngineKind::OpenTofu && at_least(1, 7),
+        }
+    }
+}
+
+/// Result of probing an engine binary.
+#[derive(Clone, Debug, Serialize)]
+pub struct EngineInfo {
+    pub kind: EngineKind,
+    pub name: String,
+    pub binary: String,
+    pub installed: bool,
+    /// Bare version number, e.g. `1.9.0`.
+    pub version: Option<String>,
+    pub capabilities: EngineCapabilities,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VersionJson {
+    terraform_version: String,
+}
+
+/// Parse `1.9.0`, `v1.9.0` or `1.10.0-beta1` into its numeric parts.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.trim().trim_start_matches('v').split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|p| p.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().flatten().unwrap_or(0);
+    let patch = parts.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+/// Run `<engine> version -json` and derive the capability flags from the version.
+/// Both terraform and OpenTofu report the version under `terraform_version`.
+pub async fn detect(engine: &Engine) -> EngineInfo {
+    let mut info = EngineInfo {
+        kind: engine.kind,
+        name: engine.name().to_string(),
+        binary: engine.binary.clone(),
+        installed: false,
+        version: None,
+        capabilities: EngineCapabilities::default(),
+        error: None,
+    };
+
+    let output = match engine.command().args(["version", "-json"]).output().await {
+        Ok(output) => output,
+        Err(e) => {
+            info.error = Some(format!(
+                "{} not found: {}. Please install {} and ensure it is on your PATH.",
+                engine.name(),
+                e,
+                engine.kind.default_binary()
+            ));
+            return info;
+        }
+    };
+    if !output.status.success() {
+        info.error = Some(format!("{} version check failed", engine.name()));
+        return info;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = match serde_json::from_str::<VersionJson>(&stdout) {
+        Ok(v) => v.terraform_version,
+        // Very old releases have no `-json`; fall back to "Terraform v1.2.3"
+        Err(_) => stdout
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().last())
+            .unwrap_or("")
+            .trim_start_matches('v')
+            .to_string(),
+    };
+    info.installed = true;
+    if let Some(parsed) = parse_version(&version) {
+        info.capabilities = EngineCapabilities::for_version(engine.kind, parsed);
+    }
+    info.version = Some(version);
+    info
+}
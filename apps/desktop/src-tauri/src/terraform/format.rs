@@ -0,0 +1,361 @@
+use super::runner::{TerraformDiagnostic, TerraformPosition, TerraformRange};
+
+/// Whether a generated file is HCL that should be checked and formatted.
+/// `.tf.json`, `.gitignore` and example files are written as-is.
+pub fn is_hcl_file(filename: &str) -> bool {
+    filename.ends_with(".tf") || filename.ends_with(".tfvars")
+}
+
+/// Parse `content` and return it canonically formatted, the way `terraform fmt`
+/// would: two-space indentation by nesting depth, `=` aligned across runs of
+/// single-line attributes, no trailing whitespace and a single final newline.
+/// Comments and heredocs are kept verbatim. A parse error is returned as a
+/// diagnostic pointing at `filename`.
+pub fn check_and_format(filename: &str, content: &str) -> Result<String, Box<TerraformDiagnostic>> {
+    check_and_format_with(filename, content, format_hcl)
+}
+
+fn check_and_format_with(
+    filename: &str,
+    content: &str,
+    format: fn(&str) -> String,
+) -> Result<String, Box<TerraformDiagnostic>> {
+    let body = hcl::parse(content).map_err(|e| Box::new(syntax_diagnostic(filename, e)))?;
+
+    let formatted = format(content);
+    // The formatter only moves whitespace; if it ever changes what the file
+    // means, keep the original rather than write something different.
+    match hcl::parse(&formatted) {
+        Ok(reparsed) if reparsed == body => Ok(formatted),
+        _ => {
+            log::warn!("Formatting changed the meaning of {}; writing it unformatted", filename);
+            Ok(content.to_string())
+        }
+    }
+}
+
+fn syntax_diagnostic(filename: &str, err: hcl::Error) -> TerraformDiagnostic {
+    let (detail, range) = match &err {
+        hcl::Error::Parse(parse) => {
+            let location = parse.location();
+            let position = TerraformPosition {
+                line: location.line() as u32,
+                column: location.column() as u32,
+                byte: location.offset() as u32,
+            };
+            let range = TerraformRange {
+                filename: filename.to_string(),
+                start: position.clone(),
+                end: position,
+            };
+            (parse.message().to_string(), Some(range))
+        }
+        other => (other.to_string(), None),
+    };
+    TerraformDiagnostic {
+        severity: "error".to_string(),
+        summary: format!("Invalid HCL syntax in {}", filename),
+        detail,
+        address: None,
+        range,
+    }
+}
+
+enum Mode {
+    Code,
+    Heredoc(String),
+    BlockComment,
+}
+
+struct Line {
+    indent: usize,
+    text: String,
+    /// Byte offset of the attribute's `=` in `text`, for alignment.
+    equals: Option<usize>,
+    /// Whether the line leaves a bracket open, i.e. starts a multi-line value.
+    opens: bool,
+    /// Emitted verbatim (heredoc body, block comment, blank line).
+    raw: bool,
+}
+
+/// What a single line of code does to the nesting depth.
+struct Scan {
+    delta: i32,
+    equals: Option<usize>,
+    next_mode: Mode,
+}
+
+enum Ctx {
+    Bracket,
+    Str,
+    Interp,
+}
+
+fn scan_line(text: &str) -> Scan {
+    let bytes = text.as_bytes();
+    let mut stack: Vec<Ctx> = Vec::new();
+    let mut delta = 0;
+    let mut equals = None;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        if let Some(Ctx::Str) = stack.last() {
+            match c {
+                b'\\' => i += 1,
+                b'"' => {
+                    stack.pop();
+                }
+                b'$' | b'%' if bytes.get(i + 1) == Some(&b'{') => {
+                    stack.push(Ctx::Interp);
+                    i += 1;
+                }
+                _ => {}
+            }
+            i += 1;
+            continue;
+        }
+
+        match c {
+            b'"' => stack.push(Ctx::Str),
+            b'#' => break,
+            b'/' if bytes.get(i + 1) == Some(&b'/') => break,
+            b'/' if bytes.get(i + 1) == Some(&b'*') => match text[i + 2..].find("*/") {
+                Some(end) => i += end + 3,
+                None => {
+                    return Scan { delta, equals, next_mode: Mode::BlockComment };
+                }
+            },
+            b'<' if bytes.get(i + 1) == Some(&b'<') => {
+                let rest = text[i + 2..].trim_start_matches('-');
+                let marker = rest.trim_end();
+                if !marker.is_empty() && marker.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_') {
+                    return Scan { delta, equals, next_mode: Mode::Heredoc(marker.to_string()) };
+                }
+            }
+            b'{' | b'[' | b'(' => {
+                stack.push(Ctx::Bracket);
+                delta += 1;
+            }
+            b'}' | b']' | b')' => match stack.last() {
+                Some(Ctx::Interp) if c == b'}' => {
+                    stack.pop();
+                }
+                Some(Ctx::Bracket) => {
+                    stack.pop();
+                    delta -= 1;
+                }
+                _ => delta -= 1,
+            },
+            b'=' if stack.is_empty() && equals.is_none() => {
+                let prev = if i > 0 { bytes[i - 1] } else { b' ' };
+                let next = bytes.get(i + 1).copied().unwrap_or(b' ');
+                if !matches!(prev, b'=' | b'!' | b'<' | b'>') && !matches!(next, b'=' | b'>') && is_attribute_key(text[..i].trim_end()) {
+                    equals = Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    Scan { delta, equals, next_mode: Mode::Code }
+}
+
+/// Attribute names and object keys: identifiers or quoted strings.
+fn is_attribute_key(key: &str) -> bool {
+    if key.len() >= 2 && key.starts_with('"') && key.ends_with('"') {
+        return !key[1..key.len() - 1].contains('"');
+    }
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(ch) if ch.is_ascii_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+}
+
+fn format_hcl(content: &str) -> String {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut mode = Mode::Code;
+    let mut depth: i32 = 0;
+
+    for source in content.lines() {
+        match &mode {
+            Mode::Heredoc(marker) => {
+                if source.trim() == marker {
+                    mode = Mode::Code;
+                }
+                lines.push(Line { indent: 0, text: source.to_string(), equals: None, opens: false, raw: true });
+                continue;
+            }
+            Mode::BlockComment => {
+                if let Some(end) = source.find("*/") {
+                    // Code after the comment on the same line is rare; keep it verbatim too
+                    let rest = &source[end + 2..];
+                    depth = (depth + scan_line(rest).delta).max(0);
+                    mode = Mode::Code;
+                }
+                lines.push(Line { indent: 0, text: source.trim_end().to_string(), equals: None, opens: false, raw: true });
+                continue;
+            }
+            Mode::Code => {}
+        }
+
+        let text = source.trim();
+        if text.is_empty() {
+            lines.push(Line { indent: 0, text: String::new(), equals: None, opens: false, raw: true });
+            continue;
+        }
+
+        let closers = text.bytes().take_while(|b| matches!(b, b'}' | b']' | b')')).count() as i32;
+        let indent = (depth - closers).max(0) as usize;
+        let scan = scan_line(text);
+        depth = (depth + scan.delta).max(0);
+        mode = scan.next_mode;
+        lines.push(Line {
+            indent,
+            text: text.to_string(),
+            equals: scan.equals.filter(|_| closers == 0),
+            opens: scan.delta > 0,
+            raw: false,
+        });
+    }
+
+    align_attributes(&mut lines);
+
+    let mut out = String::with_capacity(content.len());
+    for line in &lines {
+        if !line.raw {
+            out.push_str(&"  ".repeat(line.indent));
+        }
+        out.push_str(&line.text);
+        out.push('\n');
+    }
+    let trimmed = out.trim_end_matches('\n').len();
+    out.truncate(trimmed);
+    out.push('\n');
+    out
+}
+
+/// Align `=` across consecutive attribute lines at the same depth. A run ends at
+/// any other line, and after an attribute whose value spans several lines.
+fn align_attributes(lines: &mut [Line]) {
+    let mut start = 0;
+    while start < lines.len() {
+        if lines[start].equals.is_none() {
+            start += 1;
+            continue;
+        }
+        let indent = lines[start].indent;
+        let mut end = start + 1;
+        if !lines[start].opens {
+            while end < lines.len() && lines[end].equals.is_some() && lines[end].indent == indent {
+                end += 1;
+                if lines[end - 1].opens {
+                    break;
+                }
+            }
+        }
+
+        let width = lines[start..end]
+            .iter()
+            .map(|l| l.text[..l.equals.unwrap()].trim_end().chars().count())
+            .max()
+            .unwrap_or(0);
+        for line in &mut lines[start..end] {
+            let eq = line.equals.unwrap();
+            let key = line.text[..eq].trim_end();
+            let value = line.text[eq + 1..].trim_start();
+            let pad = width - key.chars().count();
+            line.text = if value.is_empty() {
+                format!("{}{} =", key, " ".repeat(pad))
+            } else {
+                format!("{}{} = {}", key, " ".repeat(pad), value)
+            };
+        }
+        start = end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(content: &str) -> String {
+        check_and_format("main.tf", content).unwrap()
+    }
+
+    #[test]
+    fn indents_by_depth_and_aligns_attribute_runs() {
+        let input = "resource \"azurerm_resource_group\" \"main\" {\nname = \"rg\"\n   location_long = \"westeurope\"   \n\ntags = {\nenv = \"dev\"\n\"cost-center\" = \"42\"\n}\ncount = var.enabled == true ? 1 : 0\n}\n\n\n";
+        assert_eq!(
+            format(input),
+            "resource \"azurerm_resource_group\" \"main\" {\n  name          = \"rg\"\n  location_long = \"westeurope\"\n\n  tags = {\n    env           = \"dev\"\n    \"cost-center\" = \"42\"\n  }\n  count = var.enabled == true ? 1 : 0\n}\n"
+        );
+    }
+
+    #[test]
+    fn multi_line_value_ends_an_alignment_run() {
+        let input = "locals {\na = 1\nlong_name = [\n1,\n2,\n]\nb = 2\nlonger_name = 3\n}\n";
+        assert_eq!(
+            format(input),
+            "locals {\n  a         = 1\n  long_name = [\n    1,\n    2,\n  ]\n  b           = 2\n  longer_name = 3\n}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_heredoc_bodies_verbatim() {
+        let input = "resource \"a\" \"b\" {\ncustom_data = <<-EOT\n    #!/bin/bash\n  echo \"{\" // not a comment\n  x = 1\n    EOT\nname = \"x\"\n}\n";
+        assert_eq!(
+            format(input),
+            "resource \"a\" \"b\" {\n  custom_data = <<-EOT\n    #!/bin/bash\n  echo \"{\" // not a comment\n  x = 1\n    EOT\n  name = \"x\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_block_comments_verbatim() {
+        let input = "/*\n   header {\n  keep = this\n*/\nlocals {\n/* inline { */ a = 1\nbb = 2\n}\n";
+        assert_eq!(
+            format(input),
+            "/*\n   header {\n  keep = this\n*/\nlocals {\n  /* inline { */ a = 1\n  bb = 2\n}\n"
+        );
+    }
+
+    #[test]
+    fn comment_markers_inside_strings_are_code() {
+        let input = "locals {\nurl = \"https://example.com/#top\" # trailing { comment\nhash_key = \"a#b//c\" // another [\nnext = \"${var.x}//{\"\n}\n";
+        assert_eq!(
+            format(input),
+            "locals {\n  url      = \"https://example.com/#top\" # trailing { comment\n  hash_key = \"a#b//c\" // another [\n  next     = \"${var.x}//{\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn formatting_is_idempotent() {
+        let input = "variable \"x\" {\ntype = string\ndefault = \"a\"\nvalidation {\ncondition = length(var.x) > 0\nerror_message = \"Empty.\"\n}\n}\n";
+        let once = format(input);
+        assert_eq!(format(&once), once);
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_location() {
+        let err = check_and_format("net.tf", "resource \"a\" \"b\" {\n  name = \n").unwrap_err();
+        assert_eq!(err.severity, "error");
+        assert!(err.summary.contains("net.tf"));
+        let range = err.range.unwrap();
+        assert_eq!(range.filename, "net.tf");
+        assert!(range.start.line >= 2);
+    }
+
+    #[test]
+    fn falls_back_to_original_when_formatting_changes_meaning() {
+        let input = "locals {\n  a = 1\n}\n";
+        let changed = check_and_format_with("main.tf", input, |c| c.replace('1', "2")).unwrap();
+        assert_eq!(changed, input);
+
+        let broken = check_and_format_with("main.tf", input, |c| c.replace('}', "")).unwrap();
+        assert_eq!(broken, input);
+
+        // A formatter that only moves whitespace is accepted
+        let spaced = check_and_format_with("main.tf", input, |c| c.replace("  a", "    a")).unwrap();
+        assert_eq!(spaced, "locals {\n    a = 1\n}\n");
+    }
+}
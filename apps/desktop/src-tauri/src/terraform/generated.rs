@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+
+/// Files written by the last `write_terraform_files` call, relative to the project dir.
+const MANIFEST_FILE: &str = ".terrastudio/generated.json";
+
+/// Generated file path (relative to the terraform dir) -> SHA-256 of its content.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct GeneratedManifest {
+    #[serde(default)]
+    files: BTreeMap<String, String>,
+}
+
+/// What a call to `write_generated_files` changed on disk.
+#[derive(Debug, Default)]
+pub struct WriteSummary {
+    pub written: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+pub fn content_hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Write `content` to a temp file beside `path`, flush it to disk, then rename it
+/// into place so a crash never leaves a half-written file behind.
+pub async fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{}.tmp", name));
+    let result = async {
+        let mut file = tokio::fs::File::create(&tmp).await?;
+        file.write_all(content).await?;
+        // Without this the rename can reach disk before the data does
+        file.sync_all().await?;
+        drop(file);
+        tokio::fs::rename(&tmp, path).await
+    }
+    .await;
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(e);
+    }
+    sync_parent_dir(path).await
+}
+
+/// Flush the directory entry for `path` so the rename itself survives a crash.
+#[cfg(unix)]
+async fn sync_parent_dir(path: &Path) -> std::io::Result<()> {
+    match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(dir) => tokio::fs::File::open(dir).await?.sync_all().await,
+        None => Ok(()),
+    }
+}
+
+/// Directories cannot be opened for syncing on Windows; NTFS journals the rename.
+#[cfg(not(unix))]
+async fn sync_parent_dir(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+async fn read_manifest(path: &Path) -> GeneratedManifest {
+    match tokio::fs::read_to_string(path).await {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            log::warn!("Ignoring unreadable {}: {}", path.display(), e);
+            GeneratedManifest::default()
+        }),
+        Err(_) => GeneratedManifest::default(),
+    }
+}
+
+/// Write the generated files into `terraform_dir`, skipping any whose content
+/// is already on disk, and remove files from the previous generation that are
+/// no longer produced. Only files recorded in the manifest are ever removed,
+/// and only while they still hold the content TerraStudio wrote, so hand-written
+/// and hand-edited files are left alone.
+pub async fn write_generated_files(
+    project_dir: &Path,
+    terraform_dir: &Path,
+    files: &[(&String, String)],
+) -> Result<WriteSummary, String> {
+    let manifest_path = project_dir.join(MANIFEST_FILE);
+    let previous = read_manifest(&manifest_path).await;
+    let mut summary = WriteSummary::default();
+    let mut manifest = GeneratedManifest::default();
+
+    for (filepath, content) in files {
+        let path = terraform_dir.join(filepath);
+        let hash = content_hash(content.as_bytes());
+        manifest.files.insert(filepath.to_string(), hash.clone());
+
+        // Compare against the file itself, not the manifest, so edits made outside
+        // TerraStudio are still overwritten by a fresh generation
+        if let Ok(existing) = tokio::fs::read(&path).await {
+            if content_hash(&existing) == hash {
+                summary.unchanged.push(filepath.to_string());
+                continue;
+            }
+        }
+        // Create subdirectories if the path contains them (e.g., modules/net/)
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("Failed to create directory for {}: {}", filepath, e))?;
+        }
+        write_atomic(&path, content.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write {}: {}", filepath, e))?;
+        summary.written.push(filepath.to_string());
+    }
+
+    for (filepath, hash) in &previous.files {
+        if manifest.files.contains_key(filepath) {
+            continue;
+        }
+        // Stored paths are re-validated since the manifest lives on disk and may have been edited
+        if crate::security::sanitize_filepath(filepath).is_err() {
+            log::warn!("Ignoring invalid path {} in {}", filepath, MANIFEST_FILE);
+            continue;
+        }
+        let path = terraform_dir.join(filepath);
+        match tokio::fs::read(&path).await {
+            Ok(existing) if content_hash(&existing) == *hash => {
+                tokio::fs::remove_file(&path)
+                    .await
+                    .map_err(|e| format!("Failed to remove {}: {}", filepath, e))?;
+                remove_empty_parents(&path, terraform_dir).await;
+                summary.removed.push(filepath.clone());
+            }
+            Ok(_) => log::warn!("Keeping {}: it was edited since it was generated", path.display()),
+            Err(_) => {}
+        }
+    }
+
+    if let Some(parent) = manifest_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize generated file manifest: {}", e))?;
+    write_atomic(&manifest_path, json.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", MANIFEST_FILE, e))?;
+
+    Ok(summary)
+}
+
+/// Remove directories left empty by a deleted file (e.g. `modules/net/`),
+/// stopping at the terraform dir.
+async fn remove_empty_parents(path: &Path, terraform_dir: &Path) {
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if current == terraform_dir || !current.starts_with(terraform_dir) {
+            break;
+        }
+        // remove_dir fails on non-empty directories, which ends the walk
+        if tokio::fs::remove_dir(current).await.is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
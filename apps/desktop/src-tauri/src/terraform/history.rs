@@ -0,0 +1,287 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::command;
+
+use super::generated;
+use super::runner::{PlanResourceChange, ResourceChangeInfo, RunContext, TerraformDiagnostic};
+
+/// Run records live here, one `<run id>.json` per run, relative to the project dir.
+const RUNS_DIR: &str = ".terrastudio/runs";
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// One line of a run's output, exactly as the engine printed it. With `-json`
+/// stdout lines are the raw JSON messages.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RunLogLine {
+    pub stream: String,
+    pub line: String,
+}
+
+/// Output collected while a run streams. Cheap to clone into reader tasks.
+#[derive(Clone, Default)]
+pub struct RunLog(Arc<Mutex<Vec<RunLogLine>>>);
+
+impl RunLog {
+    pub fn push(&self, stream: &str, line: &str) {
+        if let Ok(mut lines) = self.0.lock() {
+            lines.push(RunLogLine {
+                stream: stream.to_string(),
+                line: line.to_string(),
+            });
+        }
+    }
+
+    fn take(&self) -> Vec<RunLogLine> {
+        self.0.lock().map(|mut l| std::mem::take(&mut *l)).unwrap_or_default()
+    }
+}
+
+/// A finished terraform run as stored in `.terrastudio/runs/`.
+/// Secrets passed through the environment are never recorded.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RunRecord {
+    /// Same ID as the run's `terraform:status` events.
+    pub id: String,
+    /// Subcommand, e.g. `plan`, `apply` or `state`.
+    pub command: String,
+    pub args: Vec<String>,
+    pub workspace: Option<String>,
+    /// Engine name and the binary that ran.
+    pub engine: String,
+    pub binary: String,
+    /// OS user who started the run.
+    pub user: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub code: i32,
+    pub success: bool,
+    pub cancelled: bool,
+    pub diagnostics: Vec<TerraformDiagnostic>,
+    pub resource_changes: Vec<ResourceChangeInfo>,
+    pub log: Vec<RunLogLine>,
+}
+
+/// A run without its diagnostics, changes and log, for listing.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RunSummary {
+    pub id: String,
+    pub command: String,
+    pub workspace: Option<String>,
+    pub engine: String,
+    pub user: Option<String>,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub code: i32,
+    pub success: bool,
+    pub cancelled: bool,
+    #[serde(default)]
+    pub change_count: usize,
+    #[serde(default)]
+    pub error_count: usize,
+}
+
+impl From<&RunRecord> for RunSummary {
+    fn from(record: &RunRecord) -> Self {
+        RunSummary {
+            id: record.id.clone(),
+            command: record.command.clone(),
+            workspace: record.workspace.clone(),
+            engine: record.engine.clone(),
+            user: record.user.clone(),
+            started_at: record.started_at,
+            finished_at: record.finished_at,
+            code: record.code,
+            success: record.success,
+            cancelled: record.cancelled,
+            change_count: record.resource_changes.len(),
+            error_count: record.diagnostics.iter().filter(|d| d.severity == "error").count(),
+        }
+    }
+}
+
+/// How a run ended, handed to [`RunRecorder::finish`].
+pub struct RunOutcome {
+    pub code: i32,
+    pub success: bool,
+    pub cancelled: bool,
+    pub diagnostics: Vec<TerraformDiagnostic>,
+    pub resource_changes: Vec<ResourceChangeInfo>,
+}
+
+/// Collapse planned changes into the per-resource shape apply results use.
+pub fn planned_changes(changes: &[PlanResourceChange]) -> Vec<ResourceChangeInfo> {
+    changes
+        .iter()
+        .map(|c| {
+            let replace = c.actions.len() == 2
+                && c.actions.iter().any(|a| a == "delete")
+                && c.actions.iter().any(|a| a == "create");
+            ResourceChangeInfo {
+                address: c.address.clone(),
+                action: if replace { "replace".into() } else { c.actions.join(",") },
+                success: true,
+                error: None,
+            }
+        })
+        .collect()
+}
+
+/// Records one streaming run. Created when the process starts; `finish` writes
+/// the record. Runs without a project dir in their context are not recorded.
+pub struct RunRecorder {
+    project_dir: Option<PathBuf>,
+    record: RunRecord,
+    log: RunLog,
+}
+
+impl RunRecorder {
+    pub fn start(ctx: &RunContext, run_id: &str, command: &str, args: &[String]) -> Self {
+        RunRecorder {
+            project_dir: ctx.project_dir.clone(),
+            record: RunRecord {
+                id: run_id.to_string(),
+                command: command.to_string(),
+                args: args.to_vec(),
+                workspace: ctx.workspace.clone(),
+                engine: ctx.engine.name().to_string(),
+                binary: ctx.engine.binary.clone(),
+                user: std::env::var("USER").or_else(|_| std::env::var("USERNAME")).ok(),
+                started_at: now_millis(),
+                finished_at: 0,
+                code: -1,
+                success: false,
+                cancelled: false,
+                diagnostics: vec![],
+                resource_changes: vec![],
+                log: vec![],
+            },
+            log: RunLog::default(),
+        }
+    }
+
+    /// Handle for the output reader tasks.
+    pub fn log(&self) -> RunLog {
+        self.log.clone()
+    }
+
+    /// Write the record. Failing to record never fails the run itself.
+    pub async fn finish(self, outcome: RunOutcome) {
+        let Some(project_dir) = self.project_dir else {
+            return;
+        };
+        let record = RunRecord {
+            finished_at: now_millis(),
+            code: outcome.code,
+            success: outcome.success,
+            cancelled: outcome.cancelled,
+            diagnostics: outcome.diagnostics,
+            resource_changes: outcome.resource_changes,
+            log: self.log.take(),
+            ..self.record
+        };
+        if let Err(e) = write_record(&project_dir, &record).await {
+            log::warn!("Failed to record terraform {} run: {}", record.command, e);
+        }
+    }
+}
+
+async fn write_record(project_dir: &Path, record: &RunRecord) -> Result<(), String> {
+    let dir = project_dir.join(RUNS_DIR);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let json = serde_json::to_string(record).map_err(|e| format!("Failed to serialize run: {}", e))?;
+    generated::write_atomic(&dir.join(format!("{}.json", record.id)), json.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write run {}: {}", record.id, e))
+}
+
+/// Validate a run ID (a UUID) before it is used as a file name.
+fn sanitize_run_id(id: &str) -> Result<&str, String> {
+    if id.is_empty() || id.len() > 64 || !id.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        return Err(format!("Invalid run ID: {}", id));
+    }
+    Ok(id)
+}
+
+/// Summaries of every recorded run, newest first. Unreadable files are skipped.
+async fn read_summaries(project_dir: &Path) -> Result<Vec<(PathBuf, RunSummary)>, String> {
+    let dir = project_dir.join(RUNS_DIR);
+    let mut entries = match tokio::fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e)),
+    };
+    let mut runs = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Ok(json) = tokio::fs::read_to_string(&path).await else {
+            continue;
+        };
+        match serde_json::from_str::<RunRecord>(&json) {
+            Ok(record) => runs.push((path, RunSummary::from(&record))),
+            Err(e) => log::warn!("Ignoring unreadable run {}: {}", path.display(), e),
+        }
+    }
+    runs.sort_by_key(|(_, summary)| std::cmp::Reverse(summary.started_at));
+    Ok(runs)
+}
+
+/// List the project's recorded runs, newest first.
+#[command]
+pub async fn terraform_runs_list(project_path: String) -> Result<Vec<RunSummary>, String> {
+    let runs = read_summaries(Path::new(&project_path)).await?;
+    Ok(runs.into_iter().map(|(_, summary)| summary).collect())
+}
+
+/// Read one run with its diagnostics, resource changes and full log.
+#[command]
+pub async fn terraform_run_read(project_path: String, run_id: String) -> Result<RunRecord, String> {
+    let run_id = sanitize_run_id(&run_id)?;
+    let path = Path::new(&project_path).join(RUNS_DIR).join(format!("{}.json", run_id));
+    let json = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read run {}: {}", run_id, e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse run {}: {}", run_id, e))
+}
+
+/// Delete recorded runs. `keep` spares the newest runs and `older_than_days` spares
+/// runs started within that many days; a run is removed only when no given limit
+/// spares it. At least one limit is required. Returns the removed run IDs.
+#[command]
+pub async fn terraform_runs_prune(
+    project_path: String,
+    keep: Option<usize>,
+    older_than_days: Option<u64>,
+) -> Result<Vec<String>, String> {
+    if keep.is_none() && older_than_days.is_none() {
+        return Err("Give keep or olderThanDays; pruning never removes the whole run history".to_string());
+    }
+    let cutoff = older_than_days.map(|days| now_millis().saturating_sub(days.saturating_mul(86_400_000)));
+    let runs = read_summaries(Path::new(&project_path)).await?;
+    let mut removed = Vec::new();
+    for (index, (path, summary)) in runs.into_iter().enumerate() {
+        let beyond_keep = keep.map_or(true, |keep| index >= keep);
+        let too_old = cutoff.map_or(true, |cutoff| summary.started_at < cutoff);
+        if !(beyond_keep && too_old) {
+            continue;
+        }
+        tokio::fs::remove_file(&path)
+            .await
+            .map_err(|e| format!("Failed to remove run {}: {}", summary.id, e))?;
+        removed.push(summary.id);
+    }
+    Ok(removed)
+}
@@ -0,0 +1,80 @@
+use serde::Deserialize;
+use std::path::Path;
+
+use super::commands::sanitize_managed_address;
+use super::generated;
+
+/// Generated file holding the `import` blocks, relative to the terraform dir.
+pub const IMPORTS_FILE: &str = "imports.tf";
+
+/// Target for `-generate-config-out`, relative to the terraform dir.
+pub const GENERATED_CONFIG_FILE: &str = "generated.tf";
+
+/// First line of a managed `imports.tf`. A hand-written file is never touched.
+const MANAGED_HEADER: &str = "# Managed by TerraStudio. Existing resources adopted into terraform state.";
+
+/// An existing resource to bring under management.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ImportSpec {
+    /// Terraform address to import into, e.g. `azurerm_resource_group.main`.
+    pub address: String,
+    /// Azure resource ID, e.g. `/subscriptions/.../resourceGroups/rg-app`.
+    pub id: String,
+}
+
+/// Validate an Azure resource ID before it is written into an HCL string.
+/// IDs are ARM paths; anything that could end the string or start an
+/// interpolation (`"`, `\`, `${`, `%{`) is rejected by the allow-list.
+pub fn sanitize_resource_id(id: &str) -> Result<&str, String> {
+    if !id.starts_with('/') {
+        return Err(format!("Azure resource ID must start with '/': {}", id));
+    }
+    if id.len() > 2048 {
+        return Err("Azure resource ID is too long".to_string());
+    }
+    for ch in id.chars() {
+        if !ch.is_ascii_alphanumeric() && !matches!(ch, '/' | '-' | '_' | '.' | '(' | ')' | ':' | '@' | '~' | '+' | '=' | ',' | ' ') {
+            return Err(format!("Azure resource ID contains invalid character '{}': {}", ch, id));
+        }
+    }
+    Ok(id)
+}
+
+fn render_imports(imports: &[ImportSpec]) -> String {
+    let mut out = String::from(MANAGED_HEADER);
+    out.push('\n');
+    for import in imports {
+        out.push_str(&format!(
+            "\nimport {{\n  to = {}\n  id = \"{}\"\n}}\n",
+            import.address, import.id
+        ));
+    }
+    out
+}
+
+/// Validate the import pairs and write them to `imports.tf`.
+pub async fn write_imports(terraform_dir: &Path, imports: &[ImportSpec]) -> Result<(), String> {
+    if imports.is_empty() {
+        return Err("No resources given to import".to_string());
+    }
+    for (i, import) in imports.iter().enumerate() {
+        sanitize_managed_address(&import.address)?;
+        sanitize_resource_id(&import.id)?;
+        if imports[..i].iter().any(|other| other.address == import.address) {
+            return Err(format!("Resource {} is imported more than once", import.address));
+        }
+    }
+
+    let path = terraform_dir.join(IMPORTS_FILE);
+    if let Ok(content) = tokio::fs::read_to_string(&path).await {
+        if !content.starts_with(MANAGED_HEADER) {
+            return Err(format!(
+                "{} exists and is not managed by TerraStudio; move its import blocks elsewhere first",
+                IMPORTS_FILE
+            ));
+        }
+    }
+    generated::write_atomic(&path, render_imports(imports).as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", IMPORTS_FILE, e))
+}
@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::generated::{self, content_hash};
+use super::runner::{TerraformDiagnostic, TerraformJsonResult};
+use super::workspace;
+use crate::git::runner as git;
+
+/// Bindings for saved plans, one `<plan file>.json` each, relative to the project dir.
+const PLANS_DIR: &str = ".terrastudio/plans";
+
+/// File suffixes terraform reads configuration and variables from.
+const CONFIG_SUFFIXES: &[&str] = &[".tf", ".tf.json", ".tfvars", ".tfvars.json"];
+
+/// What a saved plan was generated from. Written after a successful plan and
+/// checked before the plan is applied.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct PlanBinding {
+    /// SHA-256 of the plan file itself.
+    plan: String,
+    /// SHA-256 of every configuration file, keyed by path relative to the terraform dir.
+    files: BTreeMap<String, String>,
+    /// Git HEAD commit of the project, when it is a repository.
+    git_head: Option<String>,
+    /// Workspace the plan was created in.
+    #[serde(default)]
+    workspace: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    created_at: u64,
+}
+
+/// Why a saved plan no longer matches the configuration on disk.
+#[derive(Clone, Debug, Serialize)]
+pub struct StalePlan {
+    pub reasons: Vec<String>,
+    /// Configuration files added, removed or modified since the plan was created.
+    pub changed_files: Vec<String>,
+    pub planned_head: Option<String>,
+    pub current_head: Option<String>,
+}
+
+/// Where the binding for `plan_file` (relative to the terraform dir) is kept.
+pub fn binding_path(project_dir: &Path, plan_file: &str) -> PathBuf {
+    let name = plan_file.replace(['/', '\\'], "_");
+    project_dir.join(PLANS_DIR).join(format!("{}.json", name))
+}
+
+/// Hash every configuration file under `terraform_dir`, skipping hidden
+/// directories such as `.terraform/`.
+async fn config_hashes(terraform_dir: &Path) -> Result<BTreeMap<String, String>, String> {
+    let mut hashes = BTreeMap::new();
+    let mut pending = vec![terraform_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let mut entries = tokio::fs::read_dir(&dir)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Ok(file_type) = entry.file_type().await else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if !name.starts_with('.') {
+                    pending.push(path);
+                }
+                continue;
+            }
+            if !CONFIG_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) {
+                continue;
+            }
+            let content = tokio::fs::read(&path)
+                .await
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let relative = path
+                .strip_prefix(terraform_dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            hashes.insert(relative, content_hash(&content));
+        }
+    }
+    Ok(hashes)
+}
+
+/// The project's git HEAD commit, or `None` outside a repository.
+async fn git_head(project_dir: &Path) -> Option<String> {
+    match git::run_git_capture_lenient(project_dir, &["rev-parse", "--verify", "HEAD"]).await {
+        Ok((head, _, true)) if !head.is_empty() => Some(head),
+        _ => None,
+    }
+}
+
+/// Drop the binding for `plan_file`, e.g. before it is regenerated or after a
+/// scratch plan is deleted.
+pub async fn forget(project_dir: &Path, plan_file: &str) {
+    let _ = tokio::fs::remove_file(binding_path(project_dir, plan_file)).await;
+}
+
+/// The configuration, git HEAD and workspace a plan is about to be generated from.
+pub struct ConfigSnapshot {
+    files: BTreeMap<String, String>,
+    git_head: Option<String>,
+    workspace: String,
+}
+
+impl ConfigSnapshot {
+    /// Add a configuration file terraform itself wrote during the plan, such as
+    /// the `-generate-config-out` file of an import plan, so it is bound to the
+    /// plan rather than reported as a change made after it.
+    pub async fn include_written(&mut self, terraform_dir: &Path, file: &str) -> Result<(), String> {
+        let path = terraform_dir.join(file);
+        match tokio::fs::read(&path).await {
+            Ok(content) => {
+                self.files.insert(file.replace('\\', "/"), content_hash(&content));
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+}
+
+/// Capture the configuration before the plan process starts, so files rewritten
+/// while terraform runs are not mistaken for what it planned. `workspace` is the
+/// run's workspace; `None` means the one selected in `terraform_dir`.
+pub async fn snapshot(
+    project_dir: &Path,
+    terraform_dir: &Path,
+    workspace: Option<&str>,
+) -> Result<ConfigSnapshot, String> {
+    let workspace = match workspace {
+        Some(workspace) => workspace.to_string(),
+        None => workspace::selected_workspace(terraform_dir).await,
+    };
+    Ok(ConfigSnapshot {
+        files: config_hashes(terraform_dir).await?,
+        git_head: git_head(project_dir).await,
+        workspace,
+    })
+}
+
+/// Bind `plan_file` (relative to `terraform_dir`), just generated, to the
+/// configuration captured by [`snapshot`] before the run.
+pub async fn record(
+    project_dir: &Path,
+    terraform_dir: &Path,
+    plan_file: &str,
+    snapshot: ConfigSnapshot,
+) -> Result<(), String> {
+    let plan = tokio::fs::read(terraform_dir.join(plan_file))
+        .await
+        .map_err(|e| format!("Failed to read plan file {}: {}", plan_file, e))?;
+    let binding = PlanBinding {
+        plan: content_hash(&plan),
+        files: snapshot.files,
+        git_head: snapshot.git_head,
+        workspace: Some(snapshot.workspace),
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    };
+
+    let path = binding_path(project_dir, plan_file);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(&binding)
+        .map_err(|e| format!("Failed to serialize plan binding: {}", e))?;
+    generated::write_atomic(&path, json.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write plan binding: {}", e))
+}
+
+/// The workspace `plan_file` was created in, when its binding records one.
+pub async fn planned_workspace(project_dir: &Path, plan_file: &str) -> Option<String> {
+    let json = tokio::fs::read_to_string(binding_path(project_dir, plan_file)).await.ok()?;
+    serde_json::from_str::<PlanBinding>(&json).ok()?.workspace
+}
+
+/// Compare `plan_file` against the configuration and git HEAD it was generated
+/// from. Returns why the plan is stale, or `None` when it is safe to apply.
+pub async fn check(project_dir: &Path, terraform_dir: &Path, plan_file: &str) -> Result<Option<StalePlan>, String> {
+    check_binding(
+        project_dir,
+        terraform_dir,
+        &terraform_dir.join(plan_file),
+        &binding_path(project_dir, plan_file),
+    )
+    .await
+}
+
+/// Like [`check`], for a plan file and binding kept elsewhere (e.g. the plan archive).
+pub async fn check_binding(
+    project_dir: &Path,
+    terraform_dir: &Path,
+    plan_path: &Path,
+    binding_path: &Path,
+) -> Result<Option<StalePlan>, String> {
+    let plan_file = plan_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut stale = StalePlan {
+        reasons: vec![],
+        changed_files: vec![],
+        planned_head: None,
+        current_head: None,
+    };
+
+    let plan = match tokio::fs::read(plan_path).await {
+        Ok(plan) => plan,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            stale.reasons.push("No saved plan found".to_string());
+            return Ok(Some(stale));
+        }
+        Err(e) => return Err(format!("Failed to read plan file {}: {}", plan_file, e)),
+    };
+    let binding: PlanBinding = match tokio::fs::read_to_string(binding_path).await {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_default(),
+        Err(_) => PlanBinding::default(),
+    };
+    if binding.plan.is_empty() {
+        stale
+            .reasons
+            .push("There is no record of the configuration this plan was created from".to_string());
+        return Ok(Some(stale));
+    }
+
+    if binding.plan != content_hash(&plan) {
+        stale.reasons.push(format!("{} was replaced after the plan was created", plan_file));
+    }
+
+    let current = config_hashes(terraform_dir).await?;
+    for (file, hash) in &binding.files {
+        if current.get(file) != Some(hash) {
+            stale.changed_files.push(file.clone());
+        }
+    }
+    for file in current.keys() {
+        if !binding.files.contains_key(file) {
+            stale.changed_files.push(file.clone());
+        }
+    }
+    stale.changed_files.sort();
+    if !stale.changed_files.is_empty() {
+        stale.reasons.push(format!(
+            "Configuration changed since the plan was created: {}",
+            stale.changed_files.join(", ")
+        ));
+    }
+
+    stale.current_head = git_head(project_dir).await;
+    stale.planned_head = binding.git_head;
+    if stale.current_head != stale.planned_head {
+        stale.reasons.push(format!(
+            "Git HEAD moved from {} to {}",
+            stale.planned_head.as_deref().unwrap_or("(none)"),
+            stale.current_head.as_deref().unwrap_or("(none)")
+        ));
+    }
+
+    Ok(if stale.reasons.is_empty() { None } else { Some(stale) })
+}
+
+/// Failed apply result for a stale plan, surfaced through the usual diagnostics.
+pub fn stale_result(stale: StalePlan) -> TerraformJsonResult {
+    TerraformJsonResult {
+        success: false,
+        code: -1,
+        diagnostics: vec![TerraformDiagnostic {
+            severity: "error".into(),
+            summary: "Plan is stale".into(),
+            detail: format!("{}. Run plan again before applying.", stale.reasons.join("; ")),
+            address: None,
+            range: None,
+        }],
+        resource_changes: vec![],
+        stale_plan: Some(stale),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn plan_that_writes_generated_config_is_not_stale() {
+        let project_dir = std::env::temp_dir().join(format!("terrastudio-integrity-{}", uuid::Uuid::new_v4()));
+        let terraform_dir = project_dir.join("terraform");
+        tokio::fs::create_dir_all(&terraform_dir).await.unwrap();
+        tokio::fs::write(terraform_dir.join("main.tf"), "resource \"a\" \"b\" {}\n").await.unwrap();
+
+        // Taken before the plan, as the runner does
+        let mut snapshot = snapshot(&project_dir, &terraform_dir, None).await.unwrap();
+        let before_plan = snapshot.files.clone();
+
+        // Terraform writes generated.tf and the plan file during the run
+        tokio::fs::write(terraform_dir.join("generated.tf"), "resource \"a\" \"c\" {}\n").await.unwrap();
+        tokio::fs::write(terraform_dir.join("tfplan"), b"plan").await.unwrap();
+        snapshot.include_written(&terraform_dir, "generated.tf").await.unwrap();
+        record(&project_dir, &terraform_dir, "tfplan", snapshot).await.unwrap();
+        assert!(check(&project_dir, &terraform_dir, "tfplan").await.unwrap().is_none());
+        assert_eq!(planned_workspace(&project_dir, "tfplan").await.as_deref(), Some("default"));
+
+        // Editing the generated file afterwards still makes the plan stale
+        tokio::fs::write(terraform_dir.join("generated.tf"), "resource \"a\" \"d\" {}\n").await.unwrap();
+        let stale = check(&project_dir, &terraform_dir, "tfplan").await.unwrap().unwrap();
+        assert_eq!(stale.changed_files, vec!["generated.tf".to_string()]);
+
+        // Without including it, the file terraform wrote reads as a change
+        let snapshot = ConfigSnapshot {
+            files: before_plan,
+            git_head: git_head(&project_dir).await,
+            workspace: "default".to_string(),
+        };
+        record(&project_dir, &terraform_dir, "tfplan", snapshot).await.unwrap();
+        let stale = check(&project_dir, &terraform_dir, "tfplan").await.unwrap().unwrap();
+        assert_eq!(stale.changed_files, vec!["generated.tf".to_string()]);
+
+        let _ = tokio::fs::remove_dir_all(&project_dir).await;
+    }
+}
@@ -0,0 +1,100 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The window currently running terraform against a project.
+#[derive(Clone, Serialize)]
+pub struct LockHolder {
+    pub window_label: String,
+    pub command: String,
+    pub acquired_at: u64, // epoch milliseconds
+}
+
+type LockMap = Arc<Mutex<HashMap<PathBuf, LockHolder>>>;
+
+/// Managed state preventing two windows from running terraform against the
+/// same project directory at once. Keyed on the canonical project path so
+/// different spellings of the same directory share a lock.
+#[derive(Default)]
+pub struct ProjectLocks {
+    held: LockMap,
+}
+
+impl ProjectLocks {
+    /// Take the lock for a project, or fail with an error naming the holder.
+    /// The lock is released when the returned guard is dropped.
+    pub fn acquire(
+        &self,
+        project_path: &str,
+        window_label: &str,
+        command: &str,
+    ) -> Result<ProjectLockGuard, String> {
+        let key = lock_key(Path::new(project_path));
+        let mut held = self
+            .held
+            .lock()
+            .map_err(|_| "Project lock table is poisoned".to_string())?;
+
+        if let Some(holder) = held.get(&key) {
+            return Err(if holder.window_label == window_label {
+                format!(
+                    "terraform {} is already running for this project in this window",
+                    holder.command
+                )
+            } else {
+                format!(
+                    "terraform {} is already running for this project in window '{}'",
+                    holder.command, holder.window_label
+                )
+            });
+        }
+
+        let acquired_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        held.insert(
+            key.clone(),
+            LockHolder {
+                window_label: window_label.to_string(),
+                command: command.to_string(),
+                acquired_at,
+            },
+        );
+
+        Ok(ProjectLockGuard {
+            key,
+            held: self.held.clone(),
+        })
+    }
+
+    /// Return the current holder of a project's lock, if any.
+    pub fn holder(&self, project_path: &str) -> Option<LockHolder> {
+        let key = lock_key(Path::new(project_path));
+        self.held.lock().ok()?.get(&key).cloned()
+    }
+}
+
+/// Releases the project lock on drop.
+pub struct ProjectLockGuard {
+    key: PathBuf,
+    held: LockMap,
+}
+
+impl Drop for ProjectLockGuard {
+    fn drop(&mut self) {
+        if let Ok(mut held) = self.held.lock() {
+            held.remove(&self.key);
+        }
+    }
+}
+
+/// Canonicalize the project path, falling back to the path as given if it
+/// cannot be resolved (terraform will then fail with a clearer error).
+pub(super) fn lock_key(project_path: &Path) -> PathBuf {
+    project_path
+        .canonicalize()
+        .unwrap_or_else(|_| project_path.to_path_buf())
+}
@@ -1,2 +1,21 @@
+pub mod archive;
+pub mod backend;
 pub mod commands;
+pub mod destroy;
+pub mod diagram;
+pub mod drift;
+pub mod engine;
+pub mod format;
+pub mod generated;
+pub mod history;
+pub mod import;
+pub mod integrity;
+pub mod lock;
+pub mod moved;
+pub mod plan;
+pub mod policy;
 pub mod runner;
+pub mod settings;
+pub mod state;
+pub mod versions;
+pub mod workspace;
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use super::commands::sanitize_managed_address;
+use super::generated;
+
+/// Generated file holding the `moved` blocks, relative to the terraform dir.
+pub const MOVED_FILE: &str = "moved.tf";
+
+/// Address map from the previous write, relative to the project dir.
+const ADDRESSES_FILE: &str = ".terrastudio/addresses.json";
+
+/// First line of a managed `moved.tf`. A hand-written file is never touched.
+const MANAGED_HEADER: &str = "# Managed by TerraStudio. Records renamed diagram nodes so terraform moves state instead of replacing.";
+
+/// A single `moved { from = ..., to = ... }` entry.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct MovedBlock {
+    pub from: String,
+    pub to: String,
+}
+
+/// What the last `write_terraform_files` call produced, persisted between writes.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct AddressSnapshot {
+    /// Node instanceId -> terraform address.
+    #[serde(default)]
+    addresses: BTreeMap<String, String>,
+    /// Moves still emitted into `moved.tf`.
+    #[serde(default)]
+    moved: Vec<MovedBlock>,
+}
+
+/// Combine the previous moves with the renames between two address maps.
+///
+/// Moves are kept as a chain (`a -> b`, then `b -> c`), which terraform follows
+/// itself. A move is dropped once it would be invalid: its `from` is declared
+/// again, or the end of its chain no longer exists in the configuration.
+fn plan_moves(
+    previous: &BTreeMap<String, String>,
+    current: &HashMap<String, String>,
+    existing: &[MovedBlock],
+) -> Vec<MovedBlock> {
+    let mut moves: Vec<MovedBlock> = existing.to_vec();
+
+    let mut renames: Vec<MovedBlock> = current
+        .iter()
+        .filter_map(|(id, to)| {
+            previous.get(id).filter(|from| *from != to).map(|from| MovedBlock {
+                from: from.clone(),
+                to: to.clone(),
+            })
+        })
+        .collect();
+    renames.sort_by(|a, b| a.from.cmp(&b.from));
+    // A newer move from the same address replaces the older one
+    moves.retain(|m| !renames.iter().any(|r| r.from == m.from));
+    moves.extend(renames);
+
+    let declared: HashSet<&str> = current.values().map(String::as_str).collect();
+    moves.retain(|m| m.from != m.to && !declared.contains(m.from.as_str()));
+
+    let next: HashMap<String, String> = moves.iter().map(|m| (m.from.clone(), m.to.clone())).collect();
+    let mut seen = HashSet::new();
+    moves.retain(|m| {
+        // Follow the chain to its final address; bail out on cycles
+        let mut end = &m.to;
+        let mut steps = 0;
+        while let Some(to) = next.get(end) {
+            end = to;
+            steps += 1;
+            if steps > next.len() {
+                return false;
+            }
+        }
+        declared.contains(end.as_str()) && seen.insert(m.from.clone())
+    });
+    moves
+}
+
+fn render_moved(moves: &[MovedBlock]) -> String {
+    let mut out = String::from(MANAGED_HEADER);
+    out.push('\n');
+    for m in moves {
+        out.push_str(&format!("\nmoved {{\n  from = {}\n  to   = {}\n}}\n", m.from, m.to));
+    }
+    out
+}
+
+/// Moves worked out ahead of a write, so invalid addresses are rejected before
+/// any file changes. Applied with [`PendingMoves::write`].
+pub struct PendingMoves {
+    addresses: BTreeMap<String, String>,
+    moves: Vec<MovedBlock>,
+}
+
+/// Validate `addresses` and combine the renames since the last write with the
+/// moves still recorded. Nothing is written.
+pub async fn plan_moved_blocks(
+    project_dir: &Path,
+    addresses: &HashMap<String, String>,
+) -> Result<PendingMoves, String> {
+    for address in addresses.values() {
+        sanitize_managed_address(address)?;
+    }
+
+    let snapshot_path = project_dir.join(ADDRESSES_FILE);
+    let snapshot: AddressSnapshot = match tokio::fs::read_to_string(&snapshot_path).await {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            log::warn!("Ignoring unreadable {}: {}", snapshot_path.display(), e);
+            AddressSnapshot::default()
+        }),
+        Err(_) => AddressSnapshot::default(),
+    };
+
+    // Stored entries are re-validated since the file lives on disk and may have been edited
+    let existing: Vec<MovedBlock> = snapshot
+        .moved
+        .into_iter()
+        .filter(|m| sanitize_managed_address(&m.from).is_ok() && sanitize_managed_address(&m.to).is_ok())
+        .collect();
+    Ok(PendingMoves {
+        moves: plan_moves(&snapshot.addresses, addresses, &existing),
+        addresses: addresses.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+    })
+}
+
+impl PendingMoves {
+    /// Update `moved.tf` and persist the new address map.
+    pub async fn write(self, project_dir: &Path, terraform_dir: &Path) -> Result<Vec<MovedBlock>, String> {
+        let moved_path = terraform_dir.join(MOVED_FILE);
+        let hand_written = match tokio::fs::read_to_string(&moved_path).await {
+            Ok(content) => !content.starts_with(MANAGED_HEADER),
+            Err(_) => false,
+        };
+        if hand_written {
+            if !self.moves.is_empty() {
+                log::warn!("Not overwriting hand-written {}", moved_path.display());
+            }
+        } else if self.moves.is_empty() {
+            if moved_path.exists() {
+                tokio::fs::remove_file(&moved_path)
+                    .await
+                    .map_err(|e| format!("Failed to remove {}: {}", MOVED_FILE, e))?;
+            }
+        } else {
+            generated::write_atomic(&moved_path, render_moved(&self.moves).as_bytes())
+                .await
+                .map_err(|e| format!("Failed to write {}: {}", MOVED_FILE, e))?;
+        }
+
+        let snapshot_path = project_dir.join(ADDRESSES_FILE);
+        let snapshot = AddressSnapshot {
+            addresses: self.addresses,
+            moved: self.moves,
+        };
+        if let Some(parent) = snapshot_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let json = serde_json::to_string_pretty(&snapshot)
+            .map_err(|e| format!("Failed to serialize address map: {}", e))?;
+        generated::write_atomic(&snapshot_path, json.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write {}: {}", ADDRESSES_FILE, e))?;
+
+        Ok(snapshot.moved)
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::runner::{self, PlanResourceChange, RunContext};
+use super::state::TerraformState;
+
+/// Typed model of `terraform show -json <planfile>`.
+/// Only the parts the app consumes are modelled; unknown fields are ignored.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TerraformPlan {
+    #[serde(default)]
+    pub format_version: String,
+    #[serde(default)]
+    pub terraform_version: String,
+    #[serde(default)]
+    pub resource_changes: Vec<ResourceChange>,
+    /// Changes detected outside of terraform since the last apply.
+    #[serde(default)]
+    pub resource_drift: Vec<ResourceChange>,
+    #[serde(default)]
+    pub output_changes: HashMap<String, Change>,
+    /// State snapshot the plan was created against.
+    #[serde(default)]
+    pub prior_state: Option<TerraformState>,
+    #[serde(default)]
+    pub errored: bool,
+    #[serde(default)]
+    pub applyable: Option<bool>,
+}
+
+/// A single entry of `resource_changes` / `resource_drift`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ResourceChange {
+    pub address: String,
+    #[serde(default)]
+    pub previous_address: Option<String>,
+    #[serde(default)]
+    pub module_address: Option<String>,
+    #[serde(default)]
+    pub mode: String,
+    #[serde(rename = "type", default)]
+    pub resource_type: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub index: Option<serde_json::Value>,
+    #[serde(default)]
+    pub provider_name: String,
+    /// Set when the change targets a deposed object rather than the current one.
+    #[serde(default)]
+    pub deposed: Option<String>,
+    pub change: Change,
+    /// Why terraform chose this action, e.g. "replace_because_cannot_update"
+    /// or "replace_by_request".
+    #[serde(default)]
+    pub action_reason: Option<String>,
+}
+
+/// A before/after change description shared by resources and outputs.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Change {
+    #[serde(default)]
+    pub actions: Vec<String>,
+    #[serde(default)]
+    pub before: Option<serde_json::Value>,
+    #[serde(default)]
+    pub after: Option<serde_json::Value>,
+    /// Mirrors `after`, with `true` for every value only known after apply.
+    #[serde(default)]
+    pub after_unknown: Option<serde_json::Value>,
+    /// Mirrors `before`/`after`, with `true` for every sensitive value.
+    #[serde(default)]
+    pub before_sensitive: Option<serde_json::Value>,
+    #[serde(default)]
+    pub after_sensitive: Option<serde_json::Value>,
+    /// Attribute paths that forced a replacement.
+    #[serde(default)]
+    pub replace_paths: Option<Vec<Vec<serde_json::Value>>>,
+    /// Set when the object is imported by this plan; holds the import ID.
+    #[serde(default)]
+    pub importing: Option<serde_json::Value>,
+}
+
+impl TerraformPlan {
+    /// Resource changes that actually do something (everything except no-op/read).
+    /// Imports count even when the imported object needs no further change.
+    pub fn actionable_changes(&self) -> impl Iterator<Item = &ResourceChange> {
+        self.resource_changes.iter().filter(|rc| {
+            rc.change.importing.is_some()
+                || (!rc.change.actions.is_empty()
+                    && rc.change.actions.iter().all(|a| a != "no-op" && a != "read"))
+        })
+    }
+
+    /// Convert the actionable changes into the flat shape used by the diff view.
+    pub fn plan_changes(&self) -> Vec<PlanResourceChange> {
+        self.actionable_changes().map(ResourceChange::to_plan_change).collect()
+    }
+}
+
+impl ResourceChange {
+    pub fn to_plan_change(&self) -> PlanResourceChange {
+        PlanResourceChange {
+            address: self.address.clone(),
+            module_address: self.module_address.clone().unwrap_or_default(),
+            actions: self.change.actions.clone(),
+            before: self.change.before.clone(),
+            after: self.change.after.clone(),
+            after_unknown: self.change.after_unknown.clone(),
+            before_sensitive: self.change.before_sensitive.clone(),
+            after_sensitive: self.change.after_sensitive.clone(),
+            replace_paths: self.change.replace_paths.clone(),
+            action_reason: self.action_reason.clone(),
+            importing: self.change.importing.clone(),
+        }
+    }
+}
+
+/// Run `terraform show -json <plan_file>` and parse the result.
+pub async fn show_plan(working_dir: &Path, ctx: &RunContext, plan_file: &Path) -> Result<TerraformPlan, String> {
+    let plan_path = plan_file.to_string_lossy().to_string();
+    let json = runner::run_terraform_capture(working_dir, ctx, "show", &["-json", &plan_path]).await?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse terraform plan JSON: {}", e))
+}
@@ -0,0 +1,464 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::plan::{self, ResourceChange, TerraformPlan};
+use super::runner::{RunContext, TerraformDiagnostic, TerraformJsonResult};
+
+/// Project policy file, relative to the project dir.
+pub const POLICY_FILE: &str = ".terrastudio/policy.json";
+
+/// `.terrastudio/policy.json`:
+///
+/// ```json
+/// { "rules": [
+///   { "id": "no-public-ips", "type": "deny", "resourceTypes": ["azurerm_public_ip"] },
+///   { "id": "no-public-blobs", "type": "attribute", "resourceTypes": ["azurerm_storage_account"],
+///     "path": "allow_nested_items_to_be_public", "equals": false },
+///   { "id": "tags", "type": "requiredTags", "tags": ["owner", "cost_center"] },
+///   { "id": "protected", "type": "protectedTag", "tag": "protected" }
+/// ] }
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Blocks the apply.
+    #[default]
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyRule {
+    pub id: String,
+    /// Shown instead of the generated description of the violation.
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub severity: Severity,
+    /// Resource types the rule applies to; `*` matches any suffix. Empty means all.
+    #[serde(default)]
+    pub resource_types: Vec<String>,
+    #[serde(flatten)]
+    pub check: Check,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Check {
+    /// Creating or updating a matching resource is a violation.
+    Deny,
+    /// An attribute of created or updated resources must equal a value or be absent.
+    /// `path` is dotted; `*` matches every list element or map value.
+    #[serde(rename_all = "camelCase")]
+    Attribute {
+        path: String,
+        #[serde(default)]
+        equals: Option<Value>,
+        #[serde(default)]
+        absent: bool,
+    },
+    /// Created or updated resources that support tags must set these tags.
+    RequiredTags { tags: Vec<String> },
+    /// Resources carrying this tag must not be deleted or replaced.
+    ProtectedTag { tag: String },
+}
+
+/// Keys every rule may set.
+const COMMON_KEYS: &[&str] = &["id", "type", "message", "severity", "resourceTypes"];
+
+/// Keys each rule type adds to [`COMMON_KEYS`].
+const CHECK_KEYS: &[(&str, &[&str])] = &[
+    ("deny", &[]),
+    ("attribute", &["path", "equals", "absent"]),
+    ("requiredTags", &["tags"]),
+    ("protectedTag", &["tag"]),
+];
+
+/// Reject keys a rule does not know. `#[serde(flatten)]` silently ignores them,
+/// and a misspelt `resourceTypes` would widen the rule to every resource type.
+fn check_rule_keys(index: usize, rule: &Value) -> Result<(), String> {
+    let Some(rule) = rule.as_object() else {
+        return Ok(());
+    };
+    let name = rule
+        .get("id")
+        .and_then(Value::as_str)
+        .map_or_else(|| format!("#{}", index + 1), str::to_string);
+    let check_keys = rule
+        .get("type")
+        .and_then(Value::as_str)
+        .and_then(|t| CHECK_KEYS.iter().find(|(name, _)| *name == t))
+        .map_or(&[][..], |(_, keys)| *keys);
+    match rule
+        .keys()
+        .find(|k| !COMMON_KEYS.contains(&k.as_str()) && !check_keys.contains(&k.as_str()))
+    {
+        Some(key) => Err(format!("Policy rule {} has unknown key {}", name, key)),
+        None => Ok(()),
+    }
+}
+
+impl Policy {
+    /// Parse and validate the contents of a policy file.
+    pub fn parse(json: &str) -> Result<Policy, String> {
+        let raw: Value =
+            serde_json::from_str(json).map_err(|e| format!("Invalid policy file {}: {}", POLICY_FILE, e))?;
+        if let Some(rules) = raw.get("rules").and_then(Value::as_array) {
+            for (index, rule) in rules.iter().enumerate() {
+                check_rule_keys(index, rule)?;
+            }
+        }
+        let policy: Policy =
+            serde_json::from_value(raw).map_err(|e| format!("Invalid policy file {}: {}", POLICY_FILE, e))?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        for rule in &self.rules {
+            if rule.id.trim().is_empty() {
+                return Err("Policy rule id cannot be empty".to_string());
+            }
+            if !ids.insert(rule.id.as_str()) {
+                return Err(format!("Duplicate policy rule id: {}", rule.id));
+            }
+            match &rule.check {
+                Check::Deny => {}
+                Check::Attribute { path, equals, absent } => {
+                    if path.trim().is_empty() {
+                        return Err(format!("Policy rule {} has an empty path", rule.id));
+                    }
+                    if equals.is_some() == *absent {
+                        return Err(format!(
+                            "Policy rule {} must set exactly one of equals or absent",
+                            rule.id
+                        ));
+                    }
+                }
+                Check::RequiredTags { tags } => {
+                    if tags.is_empty() {
+                        return Err(format!("Policy rule {} lists no tags", rule.id));
+                    }
+                }
+                Check::ProtectedTag { tag } => {
+                    if tag.is_empty() {
+                        return Err(format!("Policy rule {} has an empty tag", rule.id));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read the project's policy. Projects without a policy file have no rules.
+pub async fn read_policy(project_dir: &Path) -> Result<Policy, String> {
+    let json = match tokio::fs::read_to_string(project_dir.join(POLICY_FILE)).await {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Policy::default()),
+        Err(e) => return Err(format!("Failed to read {}: {}", POLICY_FILE, e)),
+    };
+    Policy::parse(&json)
+}
+
+fn type_matches(patterns: &[String], resource_type: &str) -> bool {
+    patterns.is_empty()
+        || patterns.iter().any(|p| match p.strip_suffix('*') {
+            Some(prefix) => resource_type.starts_with(prefix),
+            None => p == resource_type,
+        })
+}
+
+fn has_action(rc: &ResourceChange, action: &str) -> bool {
+    rc.change.actions.iter().any(|a| a == action)
+}
+
+/// Values at a dotted `path`, with `*` expanding list elements and map values.
+/// Missing segments yield nothing.
+fn values_at<'a>(value: &'a Value, path: &[&str]) -> Vec<&'a Value> {
+    let Some((segment, rest)) = path.split_first() else {
+        return vec![value];
+    };
+    let children: Vec<&Value> = match (value, *segment) {
+        (Value::Array(items), "*") => items.iter().collect(),
+        (Value::Object(map), "*") => map.values().collect(),
+        (Value::Array(items), index) => index.parse::<usize>().ok().and_then(|i| items.get(i)).into_iter().collect(),
+        (Value::Object(map), key) => map.get(key).into_iter().collect(),
+        _ => vec![],
+    };
+    children.into_iter().flat_map(|child| values_at(child, rest)).collect()
+}
+
+/// Whether any value at `path` is only known after apply.
+fn unknown_at(after_unknown: Option<&Value>, path: &[&str]) -> bool {
+    let Some(after_unknown) = after_unknown else {
+        return false;
+    };
+    // A parent marked unknown hides everything below it
+    (0..=path.len()).any(|depth| {
+        values_at(after_unknown, &path[..depth])
+            .iter()
+            .any(|v| v.as_bool() == Some(true))
+    })
+}
+
+fn tag_value<'a>(object: Option<&'a Value>, tag: &str) -> Option<&'a str> {
+    object?.get("tags")?.get(tag)?.as_str()
+}
+
+/// Why `rc` violates `rule`, or `None`.
+fn violation(rule: &PolicyRule, rc: &ResourceChange) -> Option<String> {
+    let writes = has_action(rc, "create") || has_action(rc, "update");
+    match &rule.check {
+        Check::Deny => writes.then(|| format!("{} resources are not allowed", rc.resource_type)),
+        Check::Attribute { path, equals, .. } => {
+            if !writes {
+                return None;
+            }
+            let segments: Vec<&str> = path.split('.').collect();
+            let after = rc.change.after.as_ref().unwrap_or(&Value::Null);
+            let values = values_at(after, &segments);
+            let unknown = unknown_at(rc.change.after_unknown.as_ref(), &segments);
+            match equals {
+                Some(expected) => {
+                    if unknown {
+                        Some(format!("{} must be {} but is only known after apply", path, expected))
+                    } else if values.is_empty() {
+                        Some(format!("{} must be {} but is not set", path, expected))
+                    } else {
+                        values
+                            .iter()
+                            .find(|v| **v != expected)
+                            .map(|actual| format!("{} must be {}, got {}", path, expected, actual))
+                    }
+                }
+                None => {
+                    let set = values.iter().any(|v| !v.is_null() && v.as_str() != Some(""));
+                    (unknown || set).then(|| format!("{} must not be set", path))
+                }
+            }
+        }
+        Check::RequiredTags { tags } => {
+            if !writes {
+                return None;
+            }
+            // Resources without a tags attribute do not support tagging
+            let after = rc.change.after.as_ref()?;
+            after.get("tags")?;
+            if unknown_at(rc.change.after_unknown.as_ref(), &["tags"]) {
+                return None;
+            }
+            let missing: Vec<&str> = tags
+                .iter()
+                .filter(|tag| tag_value(Some(after), tag).map_or(true, str::is_empty))
+                .map(String::as_str)
+                .collect();
+            (!missing.is_empty()).then(|| format!("missing required tags: {}", missing.join(", ")))
+        }
+        Check::ProtectedTag { tag } => {
+            if !has_action(rc, "delete") {
+                return None;
+            }
+            let value = tag_value(rc.change.before.as_ref(), tag)?;
+            if value.eq_ignore_ascii_case("false") {
+                return None;
+            }
+            Some(if has_action(rc, "create") {
+                format!("resources tagged {} must not be replaced", tag)
+            } else {
+                format!("resources tagged {} must not be deleted", tag)
+            })
+        }
+    }
+}
+
+/// Evaluate every rule against the plan's actionable changes.
+pub fn evaluate(policy: &Policy, plan: &TerraformPlan) -> Vec<TerraformDiagnostic> {
+    let mut diagnostics = Vec::new();
+    for rc in plan.actionable_changes() {
+        for rule in policy.rules.iter().filter(|r| type_matches(&r.resource_types, &rc.resource_type)) {
+            if let Some(reason) = violation(rule, rc) {
+                diagnostics.push(TerraformDiagnostic {
+                    severity: match rule.severity {
+                        Severity::Error => "error".into(),
+                        Severity::Warning => "warning".into(),
+                    },
+                    summary: format!("Policy {}: {}", rule.id, rule.message.as_deref().unwrap_or(&reason)),
+                    detail: format!("{} {}", rc.address, reason),
+                    address: Some(rc.address.clone()),
+                    range: None,
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Check the saved plan at `plan_file` against the project's policy.
+pub async fn check_plan(
+    project_dir: &Path,
+    terraform_dir: &Path,
+    ctx: &RunContext,
+    plan_file: &Path,
+) -> Result<Vec<TerraformDiagnostic>, String> {
+    let policy = read_policy(project_dir).await?;
+    if policy.rules.is_empty() {
+        return Ok(vec![]);
+    }
+    let plan = plan::show_plan(terraform_dir, ctx, plan_file).await?;
+    Ok(evaluate(&policy, &plan))
+}
+
+/// Whether any violation blocks the apply.
+pub fn is_blocking(violations: &[TerraformDiagnostic]) -> bool {
+    violations.iter().any(|d| d.severity == "error")
+}
+
+/// Failed apply result for a plan with blocking violations.
+pub fn blocked_result(violations: Vec<TerraformDiagnostic>) -> TerraformJsonResult {
+    TerraformJsonResult {
+        success: false,
+        code: -1,
+        diagnostics: violations,
+        resource_changes: vec![],
+        stale_plan: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy(rules: Value) -> Policy {
+        Policy::parse(&json!({ "rules": rules }).to_string()).unwrap()
+    }
+
+    fn plan(changes: Value) -> TerraformPlan {
+        serde_json::from_value(json!({ "resource_changes": changes })).unwrap()
+    }
+
+    fn change(address: &str, actions: &[&str], before: Value, after: Value, after_unknown: Value) -> Value {
+        let resource_type = address.split('.').next().unwrap();
+        json!({
+            "address": address,
+            "type": resource_type,
+            "change": { "actions": actions, "before": before, "after": after, "after_unknown": after_unknown }
+        })
+    }
+
+    fn addresses(diagnostics: &[TerraformDiagnostic]) -> Vec<&str> {
+        diagnostics.iter().filter_map(|d| d.address.as_deref()).collect()
+    }
+
+    #[test]
+    fn rejects_unknown_and_misspelt_keys() {
+        let misspelt = json!({ "rules": [{ "id": "ips", "type": "deny", "resourceType": ["azurerm_public_ip"] }] });
+        let err = Policy::parse(&misspelt.to_string()).unwrap_err();
+        assert!(err.contains("ips") && err.contains("resourceType"), "{}", err);
+
+        // Keys of another rule type are unknown too
+        let foreign = json!({ "rules": [{ "id": "t", "type": "deny", "tags": ["owner"] }] });
+        assert!(Policy::parse(&foreign.to_string()).is_err());
+
+        assert!(Policy::parse(&json!({ "rule": [] }).to_string()).is_err());
+        assert!(Policy::parse(&json!({ "rules": [{ "id": "x", "type": "bogus" }] }).to_string()).is_err());
+    }
+
+    #[test]
+    fn deny_flags_creates_and_updates_of_matching_types() {
+        let policy = policy(json!([{ "id": "no-ips", "type": "deny", "resourceTypes": ["azurerm_public_*"] }]));
+        let plan = plan(json!([
+            change("azurerm_public_ip.a", &["create"], json!(null), json!({}), json!({})),
+            change("azurerm_public_ip.b", &["delete"], json!({}), json!(null), json!({})),
+            change("azurerm_virtual_network.c", &["create"], json!(null), json!({}), json!({})),
+        ]));
+        let diagnostics = evaluate(&policy, &plan);
+        assert_eq!(addresses(&diagnostics), vec!["azurerm_public_ip.a"]);
+        assert_eq!(diagnostics[0].severity, "error");
+    }
+
+    #[test]
+    fn attribute_equals_checks_value_missing_and_unknown() {
+        let policy = policy(json!([{
+            "id": "https", "type": "attribute", "path": "https_only", "equals": true, "severity": "warning"
+        }]));
+        let plan = plan(json!([
+            change("azurerm_app.ok", &["create"], json!(null), json!({ "https_only": true }), json!({})),
+            change("azurerm_app.wrong", &["update"], json!({}), json!({ "https_only": false }), json!({})),
+            change("azurerm_app.missing", &["create"], json!(null), json!({}), json!({})),
+            change("azurerm_app.unknown", &["create"], json!(null), json!({}), json!({ "https_only": true })),
+        ]));
+        let diagnostics = evaluate(&policy, &plan);
+        assert_eq!(
+            addresses(&diagnostics),
+            vec!["azurerm_app.wrong", "azurerm_app.missing", "azurerm_app.unknown"]
+        );
+        assert!(diagnostics[0].detail.contains("got false"));
+        assert!(diagnostics[2].detail.contains("known after apply"));
+        assert!(!is_blocking(&diagnostics));
+    }
+
+    #[test]
+    fn attribute_absent_flags_set_and_unknown_values() {
+        let policy = policy(json!([{
+            "id": "no-ip", "type": "attribute", "path": "ip_configuration.*.public_ip", "absent": true
+        }]));
+        let plan = plan(json!([
+            change("azurerm_nic.empty", &["create"], json!(null),
+                json!({ "ip_configuration": [{ "public_ip": null }, { "public_ip": "" }] }), json!({})),
+            change("azurerm_nic.set", &["create"], json!(null),
+                json!({ "ip_configuration": [{ "public_ip": null }, { "public_ip": "1.2.3.4" }] }), json!({})),
+            change("azurerm_nic.unknown", &["create"], json!(null),
+                json!({ "ip_configuration": [{}] }), json!({ "ip_configuration": true })),
+        ]));
+        let diagnostics = evaluate(&policy, &plan);
+        assert_eq!(addresses(&diagnostics), vec!["azurerm_nic.set", "azurerm_nic.unknown"]);
+    }
+
+    #[test]
+    fn required_tags_skips_untaggable_and_unknown_tags() {
+        let policy = policy(json!([{ "id": "tags", "type": "requiredTags", "tags": ["owner", "cost_center"] }]));
+        let plan = plan(json!([
+            change("azurerm_rg.ok", &["create"], json!(null),
+                json!({ "tags": { "owner": "ops", "cost_center": "42" } }), json!({})),
+            change("azurerm_rg.partial", &["create"], json!(null),
+                json!({ "tags": { "owner": "ops", "cost_center": "" } }), json!({})),
+            change("azurerm_lock.untaggable", &["create"], json!(null), json!({ "name": "x" }), json!({})),
+            change("azurerm_rg.unknown", &["create"], json!(null), json!({ "tags": {} }), json!({ "tags": true })),
+        ]));
+        let diagnostics = evaluate(&policy, &plan);
+        assert_eq!(addresses(&diagnostics), vec!["azurerm_rg.partial"]);
+        assert!(diagnostics[0].detail.contains("cost_center"));
+        assert!(!diagnostics[0].detail.contains("owner"));
+    }
+
+    #[test]
+    fn protected_tag_blocks_delete_and_replace() {
+        let policy = policy(json!([{ "id": "protected", "type": "protectedTag", "tag": "protected" }]));
+        let protected = json!({ "tags": { "protected": "true" } });
+        let plan = plan(json!([
+            change("azurerm_db.replaced", &["delete", "create"], protected.clone(), json!({}), json!({})),
+            change("azurerm_db.deleted", &["delete"], protected.clone(), json!(null), json!({})),
+            change("azurerm_db.updated", &["update"], protected, json!({}), json!({})),
+            change("azurerm_db.opted_out", &["delete"], json!({ "tags": { "protected": "false" } }), json!(null), json!({})),
+            change("azurerm_db.untagged", &["delete"], json!({ "tags": {} }), json!(null), json!({})),
+        ]));
+        let diagnostics = evaluate(&policy, &plan);
+        assert_eq!(addresses(&diagnostics), vec!["azurerm_db.replaced", "azurerm_db.deleted"]);
+        assert!(diagnostics[0].detail.contains("must not be replaced"));
+        assert!(diagnostics[1].detail.contains("must not be deleted"));
+        assert!(is_blocking(&diagnostics));
+    }
+}
@@ -1,9 +1,17 @@
 use serde::{Deserialize, Serialize};
-use std::path::Path;
-use tauri::{AppHandle, Emitter};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 
+use super::engine::{self, Engine};
+use super::history::{self, RunOutcome, RunRecorder};
+use super::integrity::{self, StalePlan};
+use super::plan::{self, TerraformPlan};
+use super::settings::RunSettings;
+use crate::process::{ProcessKind, ProcessRegistry};
+use crate::project::commands::read_project_config;
+
 #[derive(Clone, Serialize)]
 pub struct TerraformOutput {
     pub stream: String,
@@ -20,6 +28,13 @@ pub struct TerraformExit {
 pub struct TerraformStatus {
     pub status: String,
     pub command: String,
+    /// Registry ID of the run, usable with `terraform_cancel`. Absent for runs
+    /// that cannot be cancelled (e.g. validate).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    /// Workspace the run targets, when one was selected explicitly.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
 }
 
 /// Terraform JSON output message (line-by-line from -json flag)
@@ -96,14 +111,17 @@ pub struct TerraformJsonResult {
     pub code: i32,
     pub diagnostics: Vec<TerraformDiagnostic>,
     pub resource_changes: Vec<ResourceChangeInfo>,
+    /// Set when a saved plan was refused because its configuration changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stale_plan: Option<StalePlan>,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ResourceChangeInfo {
     pub address: String,
     pub action: String,
     pub success: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
@@ -118,6 +136,19 @@ pub struct PlanResourceChange {
     pub before: Option<serde_json::Value>,
     #[serde(default)]
     pub after: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_unknown: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before_sensitive: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_sensitive: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replace_paths: Option<Vec<Vec<serde_json::Value>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_reason: Option<String>,
+    /// Present when the resource is being imported, e.g. `{"id": "/subscriptions/..."}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub importing: Option<serde_json::Value>,
 }
 
 /// Extended result from terraform plan that includes full before/after diffs.
@@ -128,27 +159,131 @@ pub struct TerraformPlanResult {
     pub diagnostics: Vec<TerraformDiagnostic>,
     pub plan_changes: Vec<PlanResourceChange>,
     pub plan_file_path: String,
+    /// Structured plan from `terraform show -json`, when the plan succeeded
+    /// and could be rendered. `plan_changes` is derived from it when present.
+    pub plan: Option<TerraformPlan>,
+    /// ID of the copy kept in the plan archive, when the plan was archived.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archive_id: Option<String>,
+}
+
+/// Per-run settings applied to every spawned terraform process.
+/// Deliberately not `Debug`: `env` may hold secret values that must never be logged.
+#[derive(Clone, Default)]
+pub struct RunContext {
+    /// Extra environment variables for the child, e.g. `TF_VAR_*` secrets.
+    pub env: Vec<(String, String)>,
+    /// Workspace to run against, passed as `TF_WORKSPACE` so that windows sharing
+    /// a project don't race on the selected workspace in `.terraform/environment`.
+    pub workspace: Option<String>,
+    /// Binary to run: terraform or OpenTofu, from `projectConfig.engine`.
+    pub engine: Engine,
+    /// Terraform root and extra flags, from `projectConfig.terraform`.
+    pub settings: RunSettings,
+    /// Project whose `.terrastudio/runs/` records streaming runs; `None` records nothing.
+    pub project_dir: Option<PathBuf>,
+}
+
+impl RunContext {
+    /// Context with the project's engine and run settings and no extra environment.
+    pub async fn for_project(project_dir: &Path) -> Result<Self, String> {
+        let project_config = read_project_config(project_dir).await?;
+        let settings = RunSettings::from_config(&project_config)?;
+        let engine = engine::engine_from_config(&project_config, &settings.terraform_dir(project_dir)).await?;
+        Ok(RunContext {
+            engine,
+            settings,
+            project_dir: Some(project_dir.to_path_buf()),
+            ..RunContext::default()
+        })
+    }
+
+    /// A process for the engine, with the context's environment applied.
+    fn command(&self, subcommand: &str) -> Command {
+        let mut cmd = self.engine.command();
+        cmd.arg(subcommand);
+        self.apply(&mut cmd);
+        cmd
+    }
+
+    fn apply(&self, cmd: &mut Command) {
+        cmd.envs(self.env.iter().map(|(k, v)| (k, v)));
+        if let Some(workspace) = &self.workspace {
+            cmd.env("TF_WORKSPACE", workspace);
+        }
+    }
+}
+
+/// Flags whose `key=value` settings may carry credentials.
+const SECRET_FLAGS: &[&str] = &["-backend-config", "-var"];
+
+/// The arguments a run passes after its subcommand, as recorded in run history.
+/// Values of `-backend-config` and `-var` settings are redacted.
+fn record_args(leading: &[String], args: &[&str]) -> Vec<String> {
+    let mut recorded = leading.to_vec();
+    let mut setting_follows = false;
+    for arg in args {
+        let arg = if setting_follows {
+            redact_setting(arg)
+        } else {
+            match arg.split_once('=') {
+                Some((flag, setting)) if SECRET_FLAGS.contains(&flag) => format!("{}={}", flag, redact_setting(setting)),
+                _ => arg.to_string(),
+            }
+        };
+        setting_follows = SECRET_FLAGS.contains(&arg.as_str());
+        recorded.push(arg);
+    }
+    recorded
+}
+
+/// `key=value` becomes `key=<redacted>`; a bare value, such as a backend config
+/// file path, is kept.
+fn redact_setting(setting: &str) -> String {
+    match setting.split_once('=') {
+        Some((key, _)) => format!("{}=<redacted>", key),
+        None => setting.to_string(),
+    }
+}
+
+/// Status string for the final `terraform:status` event of a streaming run.
+fn completion_status(success: bool, cancelled: bool) -> String {
+    if cancelled {
+        "cancelled".into()
+    } else if success {
+        "success".into()
+    } else {
+        "error".into()
+    }
+}
+
+/// Map the short `reason` of a streamed planned_change to the `action_reason`
+/// names used by `terraform show -json`, so both sources read the same.
+fn normalize_action_reason(reason: &str) -> String {
+    match reason {
+        "tainted" => "replace_because_tainted".into(),
+        "requested" => "replace_by_request".into(),
+        "cannot_update" => "replace_because_cannot_update".into(),
+        other => other.into(),
+    }
 }
 
 /// Run a terraform command and capture all output (no streaming).
 /// Used for commands like `terraform show -json` where we need the full JSON result.
 pub async fn run_terraform_capture(
     working_dir: &Path,
+    ctx: &RunContext,
     subcommand: &str,
     args: &[&str],
 ) -> Result<String, String> {
-    let mut cmd = Command::new("terraform");
-    cmd.arg(subcommand);
+    let mut cmd = ctx.command(subcommand);
     cmd.args(args);
     cmd.current_dir(working_dir);
 
-    #[cfg(target_os = "windows")]
-    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-
     let output = cmd.output().await.map_err(|e| {
         format!(
-            "Failed to run terraform {}: {}",
-            subcommand, e
+            "Failed to run {} {}: {}",
+            ctx.engine.binary, subcommand, e
         )
     })?;
 
@@ -185,6 +320,7 @@ pub async fn run_terraform_validate_json(
     app: &AppHandle,
     window_label: &str,
     working_dir: &Path,
+    ctx: &RunContext,
 ) -> Result<TerraformJsonResult, String> {
     // Emit running status
     let _ = app.emit_to(
@@ -193,23 +329,30 @@ pub async fn run_terraform_validate_json(
         TerraformStatus {
             status: "running".into(),
             command: "validate".into(),
+            run_id: None,
+            workspace: None,
         },
     );
 
-    let mut cmd = Command::new("terraform");
-    cmd.arg("validate");
+    let recorder = RunRecorder::start(ctx, &uuid::Uuid::new_v4().to_string(), "validate", &["-json".to_string()]);
+
+    let mut cmd = ctx.command("validate");
     cmd.arg("-json");
     cmd.current_dir(working_dir);
 
-    #[cfg(target_os = "windows")]
-    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-
     let output = cmd.output().await.map_err(|e| {
-        format!("Failed to run terraform validate: {}", e)
+        format!("Failed to run {} validate: {}", ctx.engine.binary, e)
     })?;
 
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
     let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    let log = recorder.log();
+    for line in stdout.lines() {
+        log.push("stdout", line);
+    }
+    for line in stderr.lines() {
+        log.push("stderr", line);
+    }
 
     // Emit stdout/stderr for the terminal pane
     if !stdout.is_empty() {
@@ -257,6 +400,8 @@ pub async fn run_terraform_validate_json(
         TerraformStatus {
             status: if success { "success".into() } else { "error".into() },
             command: "validate".into(),
+            run_id: None,
+            workspace: None,
         },
     );
 
@@ -264,11 +409,24 @@ pub async fn run_terraform_validate_json(
     let _ = app.emit_to(window_label, "terraform:exit", exit);
 
     // Parse the single JSON blob
-    let validate_result: TerraformValidateResult = serde_json::from_str(&stdout)
-        .map_err(|e| format!("Failed to parse terraform validate JSON: {}", e))?;
+    let validate_result: TerraformValidateResult = match serde_json::from_str(&stdout) {
+        Ok(result) => result,
+        Err(e) => {
+            recorder
+                .finish(RunOutcome {
+                    code,
+                    success: false,
+                    cancelled: false,
+                    diagnostics: vec![],
+                    resource_changes: vec![],
+                })
+                .await;
+            return Err(format!("Failed to parse terraform validate JSON: {}", e));
+        }
+    };
 
     // Convert to TerraformJsonResult
-    let diagnostics = validate_result
+    let diagnostics: Vec<TerraformDiagnostic> = validate_result
         .diagnostics
         .into_iter()
         .map(|d| TerraformDiagnostic {
@@ -280,11 +438,22 @@ pub async fn run_terraform_validate_json(
         })
         .collect();
 
+    recorder
+        .finish(RunOutcome {
+            code,
+            success: validate_result.valid,
+            cancelled: false,
+            diagnostics: diagnostics.clone(),
+            resource_changes: vec![],
+        })
+        .await;
+
     Ok(TerraformJsonResult {
         success: validate_result.valid,
         code,
         diagnostics,
         resource_changes: vec![],
+        stale_plan: None,
     })
 }
 
@@ -295,9 +464,13 @@ pub async fn run_terraform_json(
     app: &AppHandle,
     window_label: &str,
     working_dir: &Path,
+    ctx: &RunContext,
     subcommand: &str,
     args: &[&str],
 ) -> Result<TerraformJsonResult, String> {
+    let registry = app.state::<ProcessRegistry>();
+    let run = registry.register(window_label, ProcessKind::Terraform, subcommand);
+
     // Emit running status
     let _ = app.emit_to(
         window_label,
@@ -305,11 +478,14 @@ pub async fn run_terraform_json(
         TerraformStatus {
             status: "running".into(),
             command: subcommand.into(),
+            run_id: Some(run.run_id().to_string()),
+            workspace: ctx.workspace.clone(),
         },
     );
 
-    let mut cmd = Command::new("terraform");
-    cmd.arg(subcommand);
+    let recorder = RunRecorder::start(ctx, run.run_id(), subcommand, &record_args(&["-json".to_string()], args));
+
+    let mut cmd = ctx.command(subcommand);
     cmd.arg("-json");
     cmd.args(args);
     cmd.current_dir(working_dir);
@@ -317,15 +493,7 @@ pub async fn run_terraform_json(
     cmd.stdout(std::process::Stdio::piped());
     cmd.stderr(std::process::Stdio::piped());
 
-    #[cfg(target_os = "windows")]
-    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-
-    let mut child = cmd.spawn().map_err(|e| {
-        format!(
-            "Failed to spawn terraform: {}. Is terraform installed and on your PATH?",
-            e
-        )
-    })?;
+    let mut child = cmd.spawn().map_err(|e| ctx.engine.spawn_error(e))?;
 
     let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
     let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
@@ -334,6 +502,8 @@ pub async fn run_terraform_json(
     let app_stderr = app.clone();
     let label_stdout = window_label.to_string();
     let label_stderr = window_label.to_string();
+    let log_stdout = recorder.log();
+    let log_stderr = recorder.log();
 
     // Collect diagnostics and resource changes
     let diagnostics = std::sync::Arc::new(std::sync::Mutex::new(Vec::<TerraformDiagnostic>::new()));
@@ -347,6 +517,7 @@ pub async fn run_terraform_json(
         let reader = BufReader::new(stdout);
         let mut lines = reader.lines();
         while let Ok(Some(line)) = lines.next_line().await {
+            log_stdout.push("stdout", &line);
             // Try to parse as JSON
             if let Ok(msg) = serde_json::from_str::<TerraformJsonMessage>(&line) {
                 // Emit structured message for frontend
@@ -433,6 +604,7 @@ pub async fn run_terraform_json(
         let reader = BufReader::new(stderr);
         let mut lines = reader.lines();
         while let Ok(Some(line)) = lines.next_line().await {
+            log_stderr.push("stderr", &line);
             let _ = app_stderr.emit_to(
                 &label_stderr,
                 "terraform:stderr",
@@ -445,8 +617,8 @@ pub async fn run_terraform_json(
     });
 
     // Wait for process to complete
-    let status = child
-        .wait()
+    let (status, cancelled) = run
+        .wait(&mut child)
         .await
         .map_err(|e| format!("Failed to wait for terraform: {}", e))?;
 
@@ -455,19 +627,17 @@ pub async fn run_terraform_json(
     let _ = stderr_task.await;
 
     let code = status.code().unwrap_or(-1);
-    let success = status.success();
+    let success = !cancelled && status.success();
 
     // Emit completion status
     let _ = app.emit_to(
         window_label,
         "terraform:status",
         TerraformStatus {
-            status: if success {
-                "success".into()
-            } else {
-                "error".into()
-            },
+            status: completion_status(success, cancelled),
             command: subcommand.into(),
+            run_id: Some(run.run_id().to_string()),
+            workspace: ctx.workspace.clone(),
         },
     );
 
@@ -478,24 +648,53 @@ pub async fn run_terraform_json(
     let final_diagnostics = diagnostics.lock().map(|d| d.clone()).unwrap_or_default();
     let final_changes = resource_changes.lock().map(|c| c.clone()).unwrap_or_default();
 
+    recorder
+        .finish(RunOutcome {
+            code,
+            success,
+            cancelled,
+            diagnostics: final_diagnostics.clone(),
+            resource_changes: final_changes.clone(),
+        })
+        .await;
+
     Ok(TerraformJsonResult {
         success,
         code,
         diagnostics: final_diagnostics,
         resource_changes: final_changes,
+        stale_plan: None,
     })
 }
 
-/// Run `terraform plan -json -out=tfplan`, collecting planned_change messages
-/// with full before/after property diffs. Returns a `TerraformPlanResult`.
+/// Run `terraform plan -json -out=<plan_file>`, collecting planned_change messages
+/// with full before/after property diffs. `plan_file` is relative to `working_dir`
+/// and `args` are appended after the output flag (e.g. `-refresh-only`).
+/// Returns a `TerraformPlanResult`.
 pub async fn run_terraform_json_plan(
     app: &AppHandle,
     window_label: &str,
     working_dir: &Path,
+    ctx: &RunContext,
+    plan_file: &str,
+    args: &[&str],
 ) -> Result<TerraformPlanResult, String> {
-    let plan_file = working_dir.join("tfplan");
+    // The previous plan's binding is void once this run starts overwriting it
+    let mut snapshot = None;
+    if let Some(project_dir) = &ctx.project_dir {
+        integrity::forget(project_dir, plan_file).await;
+        match integrity::snapshot(project_dir, working_dir, ctx.workspace.as_deref()).await {
+            Ok(s) => snapshot = Some(s),
+            Err(e) => log::warn!("Failed to snapshot configuration for plan binding: {}", e),
+        }
+    }
+    let plan_relative = plan_file;
+    let plan_file = working_dir.join(plan_file);
     let plan_file_str = plan_file.to_string_lossy().to_string();
 
+    let registry = app.state::<ProcessRegistry>();
+    let run = registry.register(window_label, ProcessKind::Terraform, "plan");
+
     // Emit running status
     let _ = app.emit_to(
         window_label,
@@ -503,27 +702,28 @@ pub async fn run_terraform_json_plan(
         TerraformStatus {
             status: "running".into(),
             command: "plan".into(),
+            run_id: Some(run.run_id().to_string()),
+            workspace: ctx.workspace.clone(),
         },
     );
 
-    let mut cmd = Command::new("terraform");
-    cmd.arg("plan")
-        .arg("-json")
-        .arg(format!("-out={}", plan_file.display()));
+    let recorder = RunRecorder::start(
+        ctx,
+        run.run_id(),
+        "plan",
+        &record_args(&["-json".to_string(), format!("-out={}", plan_file.display())], args),
+    );
+
+    let mut cmd = ctx.command("plan");
+    cmd.arg("-json")
+        .arg(format!("-out={}", plan_file.display()))
+        .args(args);
     cmd.current_dir(working_dir);
     cmd.stdin(std::process::Stdio::null());
     cmd.stdout(std::process::Stdio::piped());
     cmd.stderr(std::process::Stdio::piped());
 
-    #[cfg(target_os = "windows")]
-    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-
-    let mut child = cmd.spawn().map_err(|e| {
-        format!(
-            "Failed to spawn terraform: {}. Is terraform installed and on your PATH?",
-            e
-        )
-    })?;
+    let mut child = cmd.spawn().map_err(|e| ctx.engine.spawn_error(e))?;
 
     let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
     let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
@@ -532,6 +732,8 @@ pub async fn run_terraform_json_plan(
     let app_stderr = app.clone();
     let label_stdout = window_label.to_string();
     let label_stderr = window_label.to_string();
+    let log_stdout = recorder.log();
+    let log_stderr = recorder.log();
 
     let diagnostics = std::sync::Arc::new(std::sync::Mutex::new(Vec::<TerraformDiagnostic>::new()));
     let plan_changes = std::sync::Arc::new(std::sync::Mutex::new(Vec::<PlanResourceChange>::new()));
@@ -544,6 +746,7 @@ pub async fn run_terraform_json_plan(
         let reader = BufReader::new(stdout);
         let mut lines = reader.lines();
         while let Ok(Some(line)) = lines.next_line().await {
+            log_stdout.push("stdout", &line);
             // Try to parse the full JSON line for planned_change extraction
             if let Ok(raw) = serde_json::from_str::<serde_json::Value>(&line) {
                 // Emit human-readable message for the terminal
@@ -595,6 +798,11 @@ pub async fn run_terraform_json_plan(
                             .to_string();
                         let before = change.get("before").cloned();
                         let after = change.get("after").cloned();
+                        let action_reason = change
+                            .get("reason")
+                            .and_then(|r| r.as_str())
+                            .map(normalize_action_reason);
+                        let importing = change.get("importing").cloned();
 
                         // Normalize action into actions array
                         let actions = if action == "replace" {
@@ -610,6 +818,12 @@ pub async fn run_terraform_json_plan(
                                 actions,
                                 before,
                                 after,
+                                after_unknown: None,
+                                before_sensitive: None,
+                                after_sensitive: None,
+                                replace_paths: None,
+                                action_reason,
+                                importing,
                             });
                         }
                     }
@@ -632,6 +846,7 @@ pub async fn run_terraform_json_plan(
         let reader = BufReader::new(stderr);
         let mut lines = reader.lines();
         while let Ok(Some(line)) = lines.next_line().await {
+            log_stderr.push("stderr", &line);
             let _ = app_stderr.emit_to(
                 &label_stderr,
                 "terraform:stderr",
@@ -643,8 +858,8 @@ pub async fn run_terraform_json_plan(
         }
     });
 
-    let status = child
-        .wait()
+    let (status, cancelled) = run
+        .wait(&mut child)
         .await
         .map_err(|e| format!("Failed to wait for terraform: {}", e))?;
 
@@ -653,22 +868,63 @@ pub async fn run_terraform_json_plan(
 
     let code = status.code().unwrap_or(-1);
     // terraform plan exits with code 2 when there are changes (success)
-    let success = code == 0 || code == 2;
+    let success = !cancelled && (code == 0 || code == 2);
 
     let _ = app.emit_to(
         window_label,
         "terraform:status",
         TerraformStatus {
-            status: if success { "success".into() } else { "error".into() },
+            status: completion_status(success, cancelled),
             command: "plan".into(),
+            run_id: Some(run.run_id().to_string()),
+            workspace: ctx.workspace.clone(),
         },
     );
 
     let exit = TerraformExit { code, success };
     let _ = app.emit_to(window_label, "terraform:exit", exit);
 
+    if let (Some(project_dir), Some(mut snapshot)) = (ctx.project_dir.as_ref().filter(|_| success), snapshot) {
+        // Configuration terraform generated during this plan belongs to it
+        if let Some(file) = args.iter().find_map(|a| a.strip_prefix("-generate-config-out=")) {
+            if let Err(e) = snapshot.include_written(working_dir, file).await {
+                log::warn!("Failed to hash generated configuration: {}", e);
+            }
+        }
+        if let Err(e) = integrity::record(project_dir, working_dir, plan_relative, snapshot).await {
+            log::warn!("Failed to record plan binding: {}", e);
+        }
+    }
+
     let final_diagnostics = diagnostics.lock().map(|d| d.clone()).unwrap_or_default();
-    let final_plan_changes = plan_changes.lock().map(|c| c.clone()).unwrap_or_default();
+    let mut final_plan_changes = plan_changes.lock().map(|c| c.clone()).unwrap_or_default();
+
+    // The streamed planned_change lines lack sensitive masks, unknown markers and
+    // replace reasons, so prefer the full rendering of the saved plan file.
+    let plan = if success {
+        match plan::show_plan(working_dir, ctx, &plan_file).await {
+            Ok(plan) => {
+                final_plan_changes = plan.plan_changes();
+                Some(plan)
+            }
+            Err(e) => {
+                log::warn!("Falling back to streamed plan changes: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    recorder
+        .finish(RunOutcome {
+            code,
+            success,
+            cancelled,
+            diagnostics: final_diagnostics.clone(),
+            resource_changes: history::planned_changes(&final_plan_changes),
+        })
+        .await;
 
     Ok(TerraformPlanResult {
         success,
@@ -676,40 +932,24 @@ pub async fn run_terraform_json_plan(
         diagnostics: final_diagnostics,
         plan_changes: final_plan_changes,
         plan_file_path: plan_file_str,
+        plan,
+        archive_id: None,
     })
 }
 
-/// Check if the terraform CLI is available on PATH.
-pub async fn check_terraform_installed() -> Result<String, String> {
-    let output = Command::new("terraform")
-        .arg("version")
-        .output()
-        .await
-        .map_err(|e| {
-            format!(
-                "Terraform not found: {}. Please install terraform and ensure it is on your PATH.",
-                e
-            )
-        })?;
-
-    if !output.status.success() {
-        return Err("Terraform version check failed".into());
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    // Return the first line (e.g., "Terraform v1.9.0")
-    Ok(stdout.lines().next().unwrap_or("unknown").trim().to_string())
-}
-
 /// Run a terraform subcommand in the given working directory,
 /// streaming output via Tauri events targeted to a specific window.
 pub async fn run_terraform(
     app: &AppHandle,
     window_label: &str,
     working_dir: &Path,
+    ctx: &RunContext,
     subcommand: &str,
     args: &[&str],
 ) -> Result<TerraformExit, String> {
+    let registry = app.state::<ProcessRegistry>();
+    let run = registry.register(window_label, ProcessKind::Terraform, subcommand);
+
     // Emit running status
     let _ = app.emit_to(
         window_label,
@@ -717,27 +957,21 @@ pub async fn run_terraform(
         TerraformStatus {
             status: "running".into(),
             command: subcommand.into(),
+            run_id: Some(run.run_id().to_string()),
+            workspace: ctx.workspace.clone(),
         },
     );
 
-    let mut cmd = Command::new("terraform");
-    cmd.arg(subcommand);
+    let recorder = RunRecorder::start(ctx, run.run_id(), subcommand, &record_args(&[], args));
+
+    let mut cmd = ctx.command(subcommand);
     cmd.args(args);
     cmd.current_dir(working_dir);
     cmd.stdin(std::process::Stdio::null());
     cmd.stdout(std::process::Stdio::piped());
     cmd.stderr(std::process::Stdio::piped());
 
-    // On Windows, prevent a console window from flashing
-    #[cfg(target_os = "windows")]
-    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-
-    let mut child = cmd.spawn().map_err(|e| {
-        format!(
-            "Failed to spawn terraform: {}. Is terraform installed and on your PATH?",
-            e
-        )
-    })?;
+    let mut child = cmd.spawn().map_err(|e| ctx.engine.spawn_error(e))?;
 
     let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
     let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
@@ -746,12 +980,15 @@ pub async fn run_terraform(
     let app_stderr = app.clone();
     let label_stdout = window_label.to_string();
     let label_stderr = window_label.to_string();
+    let log_stdout = recorder.log();
+    let log_stderr = recorder.log();
 
     // Spawn tasks to read stdout and stderr concurrently
     let stdout_task = tokio::spawn(async move {
         let reader = BufReader::new(stdout);
         let mut lines = reader.lines();
         while let Ok(Some(line)) = lines.next_line().await {
+            log_stdout.push("stdout", &line);
             let _ = app_stdout.emit_to(
                 &label_stdout,
                 "terraform:stdout",
@@ -767,6 +1004,7 @@ pub async fn run_terraform(
         let reader = BufReader::new(stderr);
         let mut lines = reader.lines();
         while let Ok(Some(line)) = lines.next_line().await {
+            log_stderr.push("stderr", &line);
             let _ = app_stderr.emit_to(
                 &label_stderr,
                 "terraform:stderr",
@@ -779,8 +1017,8 @@ pub async fn run_terraform(
     });
 
     // Wait for process to complete
-    let status = child
-        .wait()
+    let (status, cancelled) = run
+        .wait(&mut child)
         .await
         .map_err(|e| format!("Failed to wait for terraform: {}", e))?;
 
@@ -789,24 +1027,67 @@ pub async fn run_terraform(
     let _ = stderr_task.await;
 
     let code = status.code().unwrap_or(-1);
-    let success = status.success();
+    let success = !cancelled && status.success();
 
     // Emit completion status
     let _ = app.emit_to(
         window_label,
         "terraform:status",
         TerraformStatus {
-            status: if success {
-                "success".into()
-            } else {
-                "error".into()
-            },
+            status: completion_status(success, cancelled),
             command: subcommand.into(),
+            run_id: Some(run.run_id().to_string()),
+            workspace: ctx.workspace.clone(),
         },
     );
 
     let exit = TerraformExit { code, success };
     let _ = app.emit_to(window_label, "terraform:exit", exit.clone());
 
+    recorder
+        .finish(RunOutcome {
+            code,
+            success,
+            cancelled,
+            diagnostics: vec![],
+            resource_changes: vec![],
+        })
+        .await;
+
     Ok(exit)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_args_redacts_backend_config_and_vars() {
+        let args = [
+            "-backend-config=access_key=abc123",
+            "-backend-config=backend.hcl",
+            "-var=admin_password=hunter2",
+            "-var",
+            "token=s3cret",
+            "-backend-config",
+            "password=p4ss",
+            "-var-file=prod.tfvars",
+            "-target=azurerm_resource_group.main",
+        ];
+        assert_eq!(
+            record_args(&["-json".to_string()], &args),
+            vec![
+                "-json",
+                "-backend-config=access_key=<redacted>",
+                "-backend-config=backend.hcl",
+                "-var=admin_password=<redacted>",
+                "-var",
+                "token=<redacted>",
+                "-backend-config",
+                "password=<redacted>",
+                "-var-file=prod.tfvars",
+                "-target=azurerm_resource_group.main",
+            ]
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::project::commands::read_project_config;
+use crate::security;
+
+/// Terraform root used when the project does not configure one.
+pub const DEFAULT_ROOT: &str = "terraform";
+
+/// Extra flags a project may add to its runs, and the operations that accept them.
+/// Anything else is rejected so the project file cannot smuggle in arbitrary
+/// arguments (e.g. `-chdir` or `-state-out`).
+const ALLOWED_EXTRA_ARGS: &[(&str, &[&str])] = &[
+    ("-compact-warnings", &["plan", "apply", "destroy", "apply-plan", "refresh-only"]),
+    ("-refresh=false", &["plan", "apply", "destroy"]),
+    ("-lock=false", &["init", "plan", "apply", "destroy", "apply-plan", "refresh-only", "state"]),
+    ("-upgrade", &["init"]),
+];
+
+/// Operations that take `-parallelism`.
+const PARALLELISM_OPS: &[&str] = &["plan", "apply", "destroy", "apply-plan", "refresh-only"];
+
+/// Operations that take `-lock-timeout`.
+const LOCK_TIMEOUT_OPS: &[&str] = &["init", "plan", "apply", "destroy", "apply-plan", "refresh-only", "state"];
+
+/// `projectConfig.terraform`: where the configuration lives and how it is run.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSettings {
+    /// Terraform root relative to the project dir, e.g. `infra/azure`.
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub parallelism: Option<u32>,
+    /// Duration such as `30s` or `5m`.
+    #[serde(default)]
+    pub lock_timeout: Option<String>,
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+}
+
+impl RunSettings {
+    /// Read and validate the settings from a parsed `projectConfig`.
+    pub fn from_config(project_config: &serde_json::Value) -> Result<Self, String> {
+        let settings = match project_config.get("terraform") {
+            None | Some(serde_json::Value::Null) => RunSettings::default(),
+            Some(value) => serde_json::from_value::<RunSettings>(value.clone())
+                .map_err(|e| format!("Invalid terraform run settings: {}", e))?,
+        };
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(root) = &self.root {
+            security::sanitize_filepath(root).map_err(|e| format!("Invalid terraform root: {}", e))?;
+            if root == ".terrastudio" || root.starts_with(".terrastudio/") {
+                return Err(format!("Invalid terraform root: {}", root));
+            }
+        }
+        if let Some(parallelism) = self.parallelism {
+            if !(1..=256).contains(&parallelism) {
+                return Err(format!("Parallelism must be between 1 and 256, got {}", parallelism));
+            }
+        }
+        if let Some(timeout) = &self.lock_timeout {
+            sanitize_duration(timeout)?;
+        }
+        for arg in &self.extra_args {
+            if !ALLOWED_EXTRA_ARGS.iter().any(|(allowed, _)| allowed == arg) {
+                return Err(format!(
+                    "Extra argument {} is not allowed; supported: {}",
+                    arg,
+                    ALLOWED_EXTRA_ARGS.iter().map(|(a, _)| *a).collect::<Vec<_>>().join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The terraform root inside `project_dir`.
+    pub fn terraform_dir(&self, project_dir: &Path) -> PathBuf {
+        project_dir.join(self.root.as_deref().unwrap_or(DEFAULT_ROOT))
+    }
+
+    /// Flags for one operation: `init`, `plan`, `apply`, `destroy`, `apply-plan`
+    /// (applying a saved plan), `refresh-only` or `state` (`state mv`/`rm`).
+    pub fn flags(&self, op: &str) -> Vec<String> {
+        let mut flags = Vec::new();
+        if let Some(parallelism) = self.parallelism.filter(|_| PARALLELISM_OPS.contains(&op)) {
+            flags.push(format!("-parallelism={}", parallelism));
+        }
+        if let Some(timeout) = self.lock_timeout.as_ref().filter(|_| LOCK_TIMEOUT_OPS.contains(&op)) {
+            flags.push(format!("-lock-timeout={}", timeout));
+        }
+        for arg in &self.extra_args {
+            let applies = ALLOWED_EXTRA_ARGS
+                .iter()
+                .any(|(allowed, ops)| allowed == arg && ops.contains(&op));
+            if applies && !flags.contains(arg) {
+                flags.push(arg.clone());
+            }
+        }
+        flags
+    }
+}
+
+/// Validate a terraform duration: digits followed by `s`, `m` or `h`.
+fn sanitize_duration(value: &str) -> Result<&str, String> {
+    let digits = value.strip_suffix(['s', 'm', 'h']).unwrap_or("");
+    if digits.is_empty() || digits.len() > 6 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid lock timeout (expected e.g. 30s or 5m): {}", value));
+    }
+    Ok(value)
+}
+
+/// Read the project's run settings.
+pub async fn read_run_settings(project_dir: &Path) -> Result<RunSettings, String> {
+    RunSettings::from_config(&read_project_config(project_dir).await?)
+}
+
+/// The project's terraform root, honouring `projectConfig.terraform.root`.
+pub async fn terraform_dir(project_dir: &Path) -> Result<PathBuf, String> {
+    Ok(read_run_settings(project_dir).await?.terraform_dir(project_dir))
+}
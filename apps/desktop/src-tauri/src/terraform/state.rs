@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::runner::{self, RunContext};
+
+/// Typed model of `terraform show -json` (state representation).
+/// `values` is absent when the working directory has no state yet.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TerraformState {
+    #[serde(default)]
+    pub format_version: String,
+    #[serde(default)]
+    pub terraform_version: String,
+    #[serde(default)]
+    pub values: Option<StateValues>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct StateValues {
+    #[serde(default)]
+    pub outputs: HashMap<String, StateOutput>,
+    #[serde(default)]
+    pub root_module: StateModule,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StateOutput {
+    #[serde(default)]
+    pub value: serde_json::Value,
+    #[serde(rename = "type", default)]
+    pub output_type: Option<serde_json::Value>,
+    #[serde(default)]
+    pub sensitive: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct StateModule {
+    /// Module address, e.g. "module.net". Absent for the root module.
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub resources: Vec<StateResource>,
+    #[serde(default)]
+    pub child_modules: Vec<StateModule>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StateResource {
+    pub address: String,
+    /// "managed" or "data".
+    #[serde(default)]
+    pub mode: String,
+    #[serde(rename = "type", default)]
+    pub resource_type: String,
+    #[serde(default)]
+    pub name: String,
+    /// count (number) or for_each (string) key, if any.
+    #[serde(default)]
+    pub index: Option<serde_json::Value>,
+    #[serde(default)]
+    pub provider_name: String,
+    #[serde(default)]
+    pub schema_version: u64,
+    #[serde(default)]
+    pub values: serde_json::Value,
+    /// Mirrors `values`, with `true` for every sensitive attribute.
+    #[serde(default)]
+    pub sensitive_values: serde_json::Value,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub tainted: bool,
+    #[serde(default)]
+    pub deposed_key: Option<String>,
+}
+
+impl TerraformState {
+    /// All resources in the state, across the root and every child module.
+    pub fn resources(&self) -> Vec<&StateResource> {
+        let mut out = Vec::new();
+        if let Some(values) = &self.values {
+            values.root_module.collect_resources(&mut out);
+        }
+        out
+    }
+
+    /// Managed (non-data) resources only.
+    pub fn managed_resources(&self) -> Vec<&StateResource> {
+        self.resources()
+            .into_iter()
+            .filter(|r| r.mode == "managed")
+            .collect()
+    }
+
+    /// Index all resources by full address (e.g. "module.net.azurerm_subnet.app").
+    pub fn resources_by_address(&self) -> HashMap<&str, &StateResource> {
+        self.resources()
+            .into_iter()
+            .map(|r| (r.address.as_str(), r))
+            .collect()
+    }
+}
+
+impl StateModule {
+    fn collect_resources<'a>(&'a self, out: &mut Vec<&'a StateResource>) {
+        out.extend(self.resources.iter());
+        for child in &self.child_modules {
+            child.collect_resources(out);
+        }
+    }
+}
+
+/// Raw state file (`terraform.tfstate`, format version 4), as opposed to the
+/// `show -json` representation above. Only what `from_state_file` needs is modelled.
+#[derive(Deserialize)]
+struct StateFile {
+    version: u64,
+    #[serde(default)]
+    terraform_version: String,
+    #[serde(default)]
+    resources: Vec<StateFileResource>,
+}
+
+#[derive(Deserialize)]
+struct StateFileResource {
+    #[serde(default)]
+    module: Option<String>,
+    mode: String,
+    #[serde(rename = "type")]
+    resource_type: String,
+    name: String,
+    #[serde(default)]
+    provider: String,
+    #[serde(default)]
+    instances: Vec<StateFileInstance>,
+}
+
+#[derive(Deserialize)]
+struct StateFileInstance {
+    #[serde(default)]
+    index_key: Option<serde_json::Value>,
+    #[serde(default)]
+    schema_version: u64,
+    #[serde(default)]
+    attributes: serde_json::Value,
+    /// Paths like `[{"type": "get_attr", "value": "password"}]`.
+    #[serde(default)]
+    sensitive_attributes: Vec<Vec<serde_json::Value>>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    deposed: Option<String>,
+}
+
+impl TerraformState {
+    /// Parse either `terraform show -json` output or a raw `.tfstate` file.
+    /// Raw state is flattened into the root module; module membership is kept in
+    /// each resource's address.
+    pub fn parse(json: &str) -> Result<TerraformState, String> {
+        let raw: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| format!("Failed to parse state JSON: {}", e))?;
+        if raw.get("format_version").is_some() {
+            return serde_json::from_value(raw).map_err(|e| format!("Failed to parse terraform state JSON: {}", e));
+        }
+
+        let file: StateFile =
+            serde_json::from_value(raw).map_err(|e| format!("Failed to parse state file: {}", e))?;
+        if file.version != 4 {
+            return Err(format!("Unsupported state file version {}", file.version));
+        }
+
+        let mut resources = Vec::new();
+        for res in file.resources {
+            let prefix = res.module.as_deref().map(|m| format!("{}.", m)).unwrap_or_default();
+            let base = if res.mode == "data" {
+                format!("{}data.{}.{}", prefix, res.resource_type, res.name)
+            } else {
+                format!("{}{}.{}", prefix, res.resource_type, res.name)
+            };
+            for inst in res.instances {
+                // Only top-level attributes are marked; that is all consumers look at
+                let mut sensitive = serde_json::Map::new();
+                for path in &inst.sensitive_attributes {
+                    if let Some(key) = path.first().and_then(|step| step.get("value")).and_then(|v| v.as_str()) {
+                        sensitive.insert(key.to_string(), serde_json::Value::Bool(true));
+                    }
+                }
+                // JSON string quoting matches terraform's address syntax for `for_each` keys
+                let address = match &inst.index_key {
+                    Some(key) => format!("{}[{}]", base, serde_json::to_string(key).unwrap_or_default()),
+                    None => base.clone(),
+                };
+                resources.push(StateResource {
+                    address,
+                    mode: res.mode.clone(),
+                    resource_type: res.resource_type.clone(),
+                    name: res.name.clone(),
+                    index: inst.index_key,
+                    provider_name: res.provider.clone(),
+                    schema_version: inst.schema_version,
+                    values: inst.attributes,
+                    sensitive_values: serde_json::Value::Object(sensitive),
+                    depends_on: inst.dependencies,
+                    tainted: inst.status.as_deref() == Some("tainted"),
+                    deposed_key: inst.deposed,
+                });
+            }
+        }
+
+        Ok(TerraformState {
+            format_version: String::new(),
+            terraform_version: file.terraform_version,
+            values: Some(StateValues {
+                outputs: HashMap::new(),
+                root_module: StateModule {
+                    address: None,
+                    resources,
+                    child_modules: Vec::new(),
+                },
+            }),
+        })
+    }
+}
+
+/// Run `terraform show -json` against the current state and parse the result.
+/// The context's workspace selects which state is shown.
+pub async fn show_state(working_dir: &Path, ctx: &RunContext) -> Result<TerraformState, String> {
+    let json = runner::run_terraform_capture(working_dir, ctx, "show", &["-json"]).await?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse terraform state JSON: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_state_quotes_instance_keys_like_terraform() {
+        let state = serde_json::json!({
+            "version": 4,
+            "resources": [{
+                "module": "module.net",
+                "mode": "managed",
+                "type": "azurerm_subnet",
+                "name": "this",
+                "provider": "provider[\"registry.terraform.io/hashicorp/azurerm\"]",
+                "instances": [
+                    { "index_key": "web", "attributes": {} },
+                    { "index_key": "a \"b\" \\ c\u{1b}", "attributes": {} },
+                    { "index_key": 2, "attributes": {} },
+                ]
+            }]
+        });
+        let state = TerraformState::parse(&state.to_string()).unwrap();
+        let addresses: Vec<&str> = state.resources().iter().map(|r| r.address.as_str()).collect();
+        assert_eq!(
+            addresses,
+            vec![
+                r#"module.net.azurerm_subnet.this["web"]"#,
+                r#"module.net.azurerm_subnet.this["a \"b\" \\ c\u001b"]"#,
+                "module.net.azurerm_subnet.this[2]",
+            ]
+        );
+    }
+}
@@ -0,0 +1,667 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+use super::engine::{self, Engine, EngineKind};
+use super::settings;
+
+const APP_DIR_NAME: &str = "com.terrastudio.app";
+const VERSIONS_DIR: &str = "terraform/versions";
+
+/// Marker file in a version's directory when its archive had no SHA256SUMS to check.
+const UNVERIFIED_MARKER: &str = ".unverified";
+
+/// Largest binary accepted from a release archive; real releases are well under this.
+const MAX_BINARY_SIZE: usize = 512 * 1024 * 1024;
+
+#[cfg(target_os = "windows")]
+const BINARY_NAME: &str = "terraform.exe";
+#[cfg(not(target_os = "windows"))]
+const BINARY_NAME: &str = "terraform";
+
+/// A terraform binary in the managed cache.
+#[derive(Clone, Debug, Serialize)]
+pub struct InstalledVersion {
+    pub version: String,
+    pub path: String,
+    /// The archive was installed without a SHA256SUMS file to verify it against.
+    pub unverified: bool,
+}
+
+/// The binary a project would run with, and the constraint it was picked for.
+#[derive(Clone, Debug, Serialize)]
+pub struct ResolvedVersion {
+    pub required_version: Option<String>,
+    pub version: String,
+    pub path: String,
+}
+
+fn get_versions_dir() -> Result<PathBuf, String> {
+    let data_dir =
+        dirs::data_dir().ok_or_else(|| "Could not determine app data directory".to_string())?;
+    Ok(data_dir.join(APP_DIR_NAME).join(VERSIONS_DIR))
+}
+
+// --- Versions and constraints ---
+
+/// A terraform release version, e.g. `1.9.0` or `1.10.0-beta1`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Version {
+    parts: [u64; 3],
+    pre: Option<String>,
+}
+
+impl Version {
+    /// Parse a full or partial version. Missing minor/patch parts count as 0.
+    fn parse(s: &str) -> Option<(Version, usize)> {
+        let s = s.trim().trim_start_matches('v');
+        let (core, pre) = match s.split_once('-') {
+            Some((core, pre)) => (core, Some(pre.to_string())),
+            None => (s, None),
+        };
+        let mut parts = [0u64; 3];
+        let mut count = 0;
+        for (i, part) in core.split('.').enumerate() {
+            if i >= 3 {
+                return None;
+            }
+            parts[i] = part.parse().ok()?;
+            count += 1;
+        }
+        if let Some(pre) = &pre {
+            if pre.is_empty() || !pre.chars().all(|c| c.is_ascii_alphanumeric() || c == '.') {
+                return None;
+            }
+        }
+        Some((Version { parts, pre }, count))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.parts.cmp(&other.parts).then_with(|| match (&self.pre, &other.pre) {
+            (None, None) => Ordering::Equal,
+            // A pre-release sorts before its release
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(a), Some(b)) => a.cmp(b),
+        })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Validate a full version string before it is used as a directory name.
+fn sanitize_version(version: &str) -> Result<&str, String> {
+    match Version::parse(version) {
+        Some((_, 3)) if !version.starts_with('v') => Ok(version),
+        _ => Err(format!("Invalid terraform version: {}", version)),
+    }
+}
+
+/// Check a version against a terraform version constraint such as
+/// `>= 1.3, < 2.0` or `~> 1.5`. Pre-releases only match when named exactly.
+fn satisfies(version: &str, constraint: &str) -> Result<bool, String> {
+    let (version, _) = Version::parse(version).ok_or_else(|| format!("Invalid terraform version: {}", version))?;
+    let mut named_exactly = false;
+
+    for part in constraint.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let op = ["~>", ">=", "<=", "!=", "=", ">", "<"]
+            .into_iter()
+            .find(|op| part.starts_with(op))
+            .unwrap_or("");
+        let (want, count) = Version::parse(&part[op.len()..])
+            .ok_or_else(|| format!("Invalid version constraint: {}", constraint))?;
+
+        let ok = match op {
+            "" | "=" => {
+                named_exactly |= version == want;
+                version == want
+            }
+            "!=" => version != want,
+            ">" => version > want,
+            ">=" => version >= want,
+            "<" => version < want,
+            "<=" => version <= want,
+            // `~> 1.5` allows 1.x from 1.5; `~> 1.5.2` allows 1.5.x from 1.5.2
+            _ => {
+                let mut upper = want.parts;
+                let bump = count.saturating_sub(2);
+                upper[bump] += 1;
+                for p in upper.iter_mut().skip(bump + 1) {
+                    *p = 0;
+                }
+                version >= want && version.parts < upper
+            }
+        };
+        if !ok {
+            return Ok(false);
+        }
+    }
+    Ok(version.pre.is_none() || named_exactly)
+}
+
+/// Collect `required_version` from the `terraform {}` blocks of the top-level
+/// `.tf` files. Multiple constraints are combined, as terraform does.
+pub async fn read_required_version(terraform_dir: &Path) -> Result<Option<String>, String> {
+    let mut entries = match tokio::fs::read_dir(terraform_dir).await {
+        Ok(entries) => entries,
+        Err(_) => return Ok(None),
+    };
+    let mut files = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "tf") && path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    let mut constraints = Vec::new();
+    for path in files {
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let body = match hcl::parse(&content) {
+            Ok(body) => body,
+            Err(e) => {
+                log::warn!("Skipping {} while reading required_version: {}", path.display(), e);
+                continue;
+            }
+        };
+        for block in body.blocks().filter(|b| b.identifier() == "terraform") {
+            for attr in block.body().attributes().filter(|a| a.key() == "required_version") {
+                if let hcl::Expression::String(constraint) = attr.expr() {
+                    constraints.push(constraint.clone());
+                }
+            }
+        }
+    }
+    Ok(if constraints.is_empty() { None } else { Some(constraints.join(", ")) })
+}
+
+// --- Cache ---
+
+/// Installed versions, newest first.
+pub async fn list_installed() -> Result<Vec<InstalledVersion>, String> {
+    let dir = get_versions_dir()?;
+    let mut entries = match tokio::fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(_) => return Ok(Vec::new()),
+    };
+    let mut versions: Vec<(Version, InstalledVersion)> = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let binary = entry.path().join(BINARY_NAME);
+        if sanitize_version(&name).is_err() || !binary.is_file() {
+            continue;
+        }
+        if let Some((parsed, _)) = Version::parse(&name) {
+            versions.push((
+                parsed,
+                InstalledVersion {
+                    version: name,
+                    path: binary.to_string_lossy().to_string(),
+                    unverified: entry.path().join(UNVERIFIED_MARKER).exists(),
+                },
+            ));
+        }
+    }
+    versions.sort_by(|a, b| b.0.cmp(&a.0));
+    Ok(versions.into_iter().map(|(_, v)| v).collect())
+}
+
+/// Pick the newest installed binary that satisfies the project's `required_version`.
+pub async fn resolve(terraform_dir: &Path) -> Result<ResolvedVersion, String> {
+    let required = read_required_version(terraform_dir).await?;
+    let installed = list_installed().await?;
+    if installed.is_empty() {
+        return Err("No managed terraform versions are installed".to_string());
+    }
+
+    let mut chosen = None;
+    for candidate in &installed {
+        let ok = match &required {
+            Some(constraint) => satisfies(&candidate.version, constraint)?,
+            // Without a constraint, prefer the newest stable release
+            None => Version::parse(&candidate.version).is_some_and(|(v, _)| v.pre.is_none()),
+        };
+        if ok {
+            chosen = Some(candidate);
+            break;
+        }
+    }
+
+    match chosen {
+        Some(v) => Ok(ResolvedVersion {
+            required_version: required,
+            version: v.version.clone(),
+            path: v.path.clone(),
+        }),
+        None => Err(format!(
+            "No installed terraform version satisfies required_version \"{}\" (installed: {})",
+            required.unwrap_or_default(),
+            installed.iter().map(|v| v.version.as_str()).collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
+// --- Installing ---
+
+/// Platform suffix of the release archives, e.g. `linux_amd64`.
+fn platform() -> String {
+    let os = match std::env::consts::OS {
+        "macos" => "darwin",
+        other => other,
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        other => other,
+    };
+    format!("{}_{}", os, arch)
+}
+
+/// Split `terraform_1.9.0_linux_amd64.zip` into version and platform.
+fn parse_archive_name(name: &str) -> Option<(&str, &str)> {
+    let rest = name.strip_prefix("terraform_")?.strip_suffix(".zip")?;
+    let (version, platform) = rest.split_once('_')?;
+    Some((version, platform))
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<usize> {
+    data.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    data.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Extract a single file from a zip archive held in memory. Release archives
+/// are small, stored or deflated, and never need zip64.
+fn extract_zip_entry(data: &[u8], wanted: &str) -> Result<Vec<u8>, String> {
+    const EOCD: u32 = 0x0605_4b50;
+    const CENTRAL: u32 = 0x0201_4b50;
+    const LOCAL: u32 = 0x0403_4b50;
+    let corrupt = || "Archive is not a valid zip file".to_string();
+
+    let min = data.len().saturating_sub(22 + u16::MAX as usize);
+    let eocd = (min..data.len().saturating_sub(21))
+        .rev()
+        .find(|&i| read_u32(data, i) == Some(EOCD))
+        .ok_or_else(corrupt)?;
+    let entries = read_u16(data, eocd + 10).ok_or_else(corrupt)?;
+    let mut at = read_u32(data, eocd + 16).ok_or_else(corrupt)? as usize;
+
+    for _ in 0..entries {
+        if read_u32(data, at) != Some(CENTRAL) {
+            return Err(corrupt());
+        }
+        let method = read_u16(data, at + 10).ok_or_else(corrupt)?;
+        let crc = read_u32(data, at + 16).ok_or_else(corrupt)?;
+        let compressed = read_u32(data, at + 20).ok_or_else(corrupt)? as usize;
+        let size = read_u32(data, at + 24).ok_or_else(corrupt)? as usize;
+        let name_len = read_u16(data, at + 28).ok_or_else(corrupt)?;
+        let extra_len = read_u16(data, at + 30).ok_or_else(corrupt)?;
+        let comment_len = read_u16(data, at + 32).ok_or_else(corrupt)?;
+        let local = read_u32(data, at + 42).ok_or_else(corrupt)? as usize;
+        let name = data.get(at + 46..at + 46 + name_len).ok_or_else(corrupt)?;
+        at += 46 + name_len + extra_len + comment_len;
+
+        if name != wanted.as_bytes() {
+            continue;
+        }
+        if read_u32(data, local) != Some(LOCAL) {
+            return Err(corrupt());
+        }
+        let start = local + 30 + read_u16(data, local + 26).ok_or_else(corrupt)? + read_u16(data, local + 28).ok_or_else(corrupt)?;
+        if size > MAX_BINARY_SIZE {
+            return Err(format!("{} in archive is too large", wanted));
+        }
+        let raw = data.get(start..start + compressed).ok_or_else(corrupt)?;
+        let content = match method {
+            0 => raw.to_vec(),
+            8 => {
+                // The header sizes are untrusted: read at most one byte past the
+                // declared size so an oversized stream fails the length check below
+                let mut out = Vec::new();
+                flate2::read::DeflateDecoder::new(raw)
+                    .take(size as u64 + 1)
+                    .read_to_end(&mut out)
+                    .map_err(|e| format!("Failed to decompress {}: {}", wanted, e))?;
+                out
+            }
+            other => return Err(format!("Unsupported zip compression method {}", other)),
+        };
+        if content.len() != size || crc32fast::hash(&content) != crc {
+            return Err(format!("Checksum mismatch for {} in archive", wanted));
+        }
+        return Ok(content);
+    }
+    Err(format!("Archive does not contain {}", wanted))
+}
+
+/// Verify the archive against a `terraform_<version>_SHA256SUMS` file beside it,
+/// when the mirror provides one. Returns whether a checksum was checked.
+async fn verify_checksum(zip_path: &Path, version: &str, data: &[u8]) -> Result<bool, String> {
+    let sums_path = zip_path.with_file_name(format!("terraform_{}_SHA256SUMS", version));
+    let Ok(sums) = tokio::fs::read_to_string(&sums_path).await else {
+        log::warn!("No {} beside {}; installing unverified", sums_path.display(), zip_path.display());
+        return Ok(false);
+    };
+    let file_name = zip_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let expected = sums
+        .lines()
+        .find_map(|line| {
+            let (hash, name) = line.split_once(char::is_whitespace)?;
+            (name.trim().trim_start_matches('*') == file_name).then(|| hash.to_lowercase())
+        })
+        .ok_or_else(|| format!("{} does not list {}", sums_path.display(), file_name))?;
+    if format!("{:x}", Sha256::digest(data)) != expected {
+        return Err(format!("SHA-256 of {} does not match {}", file_name, sums_path.display()));
+    }
+    Ok(true)
+}
+
+/// Install a terraform release archive into the cache and confirm that the
+/// extracted binary reports the expected version.
+async fn install_archive(zip_path: &Path) -> Result<InstalledVersion, String> {
+    let file_name = zip_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let (version, archive_platform) = parse_archive_name(&file_name).ok_or_else(|| {
+        format!(
+            "Expected a terraform release archive named terraform_<version>_<os>_<arch>.zip, got {}",
+            file_name
+        )
+    })?;
+    let version = sanitize_version(version)?.to_string();
+    if archive_platform != platform() {
+        return Err(format!(
+            "{} is built for {}, but this machine needs {}",
+            file_name,
+            archive_platform,
+            platform()
+        ));
+    }
+
+    let data = tokio::fs::read(zip_path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", zip_path.display(), e))?;
+    let verified = verify_checksum(zip_path, &version, &data).await?;
+    let binary = extract_zip_entry(&data, BINARY_NAME)?;
+
+    let dir = get_versions_dir()?.join(&version);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let target = dir.join(BINARY_NAME);
+    let tmp = dir.join(format!(".{}.tmp", BINARY_NAME));
+    tokio::fs::write(&tmp, &binary)
+        .await
+        .map_err(|e| format!("Failed to write terraform binary: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o755))
+            .await
+            .map_err(|e| format!("Failed to make terraform binary executable: {}", e))?;
+    }
+    tokio::fs::rename(&tmp, &target)
+        .await
+        .map_err(|e| format!("Failed to install terraform binary: {}", e))?;
+    let marker = dir.join(UNVERIFIED_MARKER);
+    if verified {
+        let _ = tokio::fs::remove_file(&marker).await;
+    } else {
+        tokio::fs::write(&marker, b"")
+            .await
+            .map_err(|e| format!("Failed to record unverified install: {}", e))?;
+    }
+
+    let engine = Engine {
+        kind: EngineKind::Terraform,
+        binary: target.to_string_lossy().to_string(),
+    };
+    let info = engine::detect(&engine).await;
+    if info.version.as_deref() != Some(version.as_str()) {
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        return Err(format!(
+            "Installed binary reports version {} instead of {}",
+            info.version.unwrap_or_else(|| "unknown".to_string()),
+            version
+        ));
+    }
+
+    Ok(InstalledVersion {
+        version,
+        path: engine.binary,
+        unverified: !verified,
+    })
+}
+
+// --- Commands ---
+
+/// List the terraform versions in the managed cache, newest first.
+#[command]
+pub async fn terraform_versions_list() -> Result<Vec<InstalledVersion>, String> {
+    list_installed().await
+}
+
+/// Install terraform from a local release archive (`terraform_<version>_<os>_<arch>.zip`).
+#[command]
+pub async fn terraform_version_install_zip(zip_path: String) -> Result<InstalledVersion, String> {
+    install_archive(Path::new(&zip_path)).await
+}
+
+/// Install a terraform version from a mirror directory laid out like the release
+/// site (`<mirror>/<version>/terraform_<version>_<os>_<arch>.zip`) or holding the
+/// archives directly.
+#[command]
+pub async fn terraform_version_install_mirror(mirror_dir: String, version: String) -> Result<InstalledVersion, String> {
+    sanitize_version(&version)?;
+    let archive = format!("terraform_{}_{}.zip", version, platform());
+    let mirror = PathBuf::from(&mirror_dir);
+    let zip_path = [mirror.join(&version).join(&archive), mirror.join(&archive)]
+        .into_iter()
+        .find(|p| p.is_file())
+        .ok_or_else(|| format!("{} not found in mirror {}", archive, mirror_dir))?;
+    install_archive(&zip_path).await
+}
+
+/// Remove a version from the managed cache.
+#[command]
+pub async fn terraform_version_remove(version: String) -> Result<(), String> {
+    sanitize_version(&version)?;
+    let dir = get_versions_dir()?.join(&version);
+    if !dir.exists() {
+        return Err(format!("Terraform {} is not installed", version));
+    }
+    tokio::fs::remove_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to remove terraform {}: {}", version, e))
+}
+
+/// Show which managed binary the project's `required_version` resolves to.
+#[command]
+pub async fn terraform_version_resolve(project_path: String) -> Result<ResolvedVersion, String> {
+    resolve(&settings::terraform_dir(Path::new(&project_path)).await?).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn parse(s: &str) -> (Vec<u64>, Option<String>, usize) {
+        let (v, count) = Version::parse(s).unwrap();
+        (v.parts.to_vec(), v.pre, count)
+    }
+
+    #[test]
+    fn parse_full_partial_and_pre_release() {
+        assert_eq!(parse("1.9.0"), (vec![1, 9, 0], None, 3));
+        assert_eq!(parse("v1.5"), (vec![1, 5, 0], None, 2));
+        assert_eq!(parse(" 1 "), (vec![1, 0, 0], None, 1));
+        assert_eq!(parse("1.10.0-beta1"), (vec![1, 10, 0], Some("beta1".to_string()), 3));
+        assert_eq!(parse("1.10.0-rc.1"), (vec![1, 10, 0], Some("rc.1".to_string()), 3));
+    }
+
+    #[test]
+    fn parse_rejects_malformed() {
+        for bad in ["", "1.2.3.4", "1.x", "1.2.3-", "1.2.3-beta/1", "1..2"] {
+            assert!(Version::parse(bad).is_none(), "{} should not parse", bad);
+        }
+        assert!(sanitize_version("1.9").is_err());
+        assert!(sanitize_version("v1.9.0").is_err());
+        assert!(sanitize_version("1.9.0").is_ok());
+    }
+
+    #[test]
+    fn pre_release_sorts_before_release() {
+        let (beta, _) = Version::parse("1.10.0-beta1").unwrap();
+        let (release, _) = Version::parse("1.10.0").unwrap();
+        let (older, _) = Version::parse("1.9.8").unwrap();
+        assert!(beta < release);
+        assert!(older < beta);
+    }
+
+    #[test]
+    fn pessimistic_constraint_with_one_two_and_three_parts() {
+        assert!(satisfies("1.0.0", "~> 1").unwrap());
+        assert!(satisfies("1.9.8", "~> 1").unwrap());
+        assert!(!satisfies("2.0.0", "~> 1").unwrap());
+
+        assert!(satisfies("1.5.0", "~> 1.5").unwrap());
+        assert!(satisfies("1.9.0", "~> 1.5").unwrap());
+        assert!(!satisfies("1.4.9", "~> 1.5").unwrap());
+        assert!(!satisfies("2.0.0", "~> 1.5").unwrap());
+
+        assert!(satisfies("1.5.2", "~> 1.5.2").unwrap());
+        assert!(satisfies("1.5.9", "~> 1.5.2").unwrap());
+        assert!(!satisfies("1.5.1", "~> 1.5.2").unwrap());
+        assert!(!satisfies("1.6.0", "~> 1.5.2").unwrap());
+    }
+
+    #[test]
+    fn combined_and_comparison_constraints() {
+        assert!(satisfies("1.6.0", ">= 1.3, < 2.0").unwrap());
+        assert!(!satisfies("2.0.0", ">= 1.3, < 2.0").unwrap());
+        assert!(!satisfies("1.2.9", ">= 1.3, < 2.0").unwrap());
+        assert!(satisfies("1.5.0", "= 1.5").unwrap());
+        assert!(satisfies("1.5.0", "1.5.0").unwrap());
+        assert!(!satisfies("1.5.0", "!= 1.5.0").unwrap());
+        assert!(satisfies("1.5.1", "> 1.5.0, <= 1.5.1").unwrap());
+        assert!(satisfies("1.5.0", " , >= 1.0").unwrap());
+        assert!(satisfies("1.5.0", "~> 1.x").is_err());
+        assert!(satisfies("latest", ">= 1.0").is_err());
+    }
+
+    #[test]
+    fn pre_release_matches_only_when_named_exactly() {
+        assert!(!satisfies("1.10.0-beta1", ">= 1.9").unwrap());
+        assert!(!satisfies("1.10.0-beta1", "~> 1.9").unwrap());
+        assert!(satisfies("1.10.0-beta1", "1.10.0-beta1").unwrap());
+        assert!(satisfies("1.10.0-beta1", "= 1.10.0-beta1, >= 1.9").unwrap());
+        assert!(!satisfies("1.10.0-beta2", "= 1.10.0-beta1").unwrap());
+    }
+
+    /// A one-entry zip archive. `method` 0 stores, 8 deflates; `crc` overrides the
+    /// recorded checksum and `size` the recorded uncompressed size.
+    fn zip(name: &str, content: &[u8], method: u16, crc: Option<u32>, size: Option<u32>) -> Vec<u8> {
+        let compressed = match method {
+            8 => {
+                let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(content).unwrap();
+                encoder.finish().unwrap()
+            }
+            _ => content.to_vec(),
+        };
+        let crc = crc.unwrap_or_else(|| crc32fast::hash(content));
+        let size = size.unwrap_or(content.len() as u32);
+
+        let mut out = Vec::new();
+        out.extend(0x0403_4b50u32.to_le_bytes());
+        out.extend([20, 0, 0, 0]);
+        out.extend(method.to_le_bytes());
+        out.extend([0; 4]);
+        out.extend(crc.to_le_bytes());
+        out.extend((compressed.len() as u32).to_le_bytes());
+        out.extend(size.to_le_bytes());
+        out.extend((name.len() as u16).to_le_bytes());
+        out.extend([0, 0]);
+        out.extend(name.as_bytes());
+        out.extend(&compressed);
+
+        let central = out.len() as u32;
+        out.extend(0x0201_4b50u32.to_le_bytes());
+        out.extend([20, 0, 20, 0, 0, 0]);
+        out.extend(method.to_le_bytes());
+        out.extend([0; 4]);
+        out.extend(crc.to_le_bytes());
+        out.extend((compressed.len() as u32).to_le_bytes());
+        out.extend(size.to_le_bytes());
+        out.extend((name.len() as u16).to_le_bytes());
+        out.extend([0; 12]);
+        out.extend(0u32.to_le_bytes());
+        out.extend(name.as_bytes());
+        let central_len = out.len() as u32 - central;
+
+        out.extend(0x0605_4b50u32.to_le_bytes());
+        out.extend([0; 4]);
+        out.extend(1u16.to_le_bytes());
+        out.extend(1u16.to_le_bytes());
+        out.extend(central_len.to_le_bytes());
+        out.extend(central.to_le_bytes());
+        out.extend([0, 0]);
+        out
+    }
+
+    #[test]
+    fn extracts_stored_and_deflated_entries() {
+        let content = b"#!/bin/sh\necho terraform\n".repeat(50);
+        assert_eq!(extract_zip_entry(&zip("terraform", &content, 0, None, None), "terraform").unwrap(), content);
+        assert_eq!(extract_zip_entry(&zip("terraform", &content, 8, None, None), "terraform").unwrap(), content);
+    }
+
+    #[test]
+    fn rejects_crc_and_size_mismatches() {
+        let content = b"terraform binary".to_vec();
+        let bad_crc = zip("terraform", &content, 8, Some(crc32fast::hash(&content) ^ 1), None);
+        assert!(extract_zip_entry(&bad_crc, "terraform").unwrap_err().contains("Checksum mismatch"));
+
+        // A deflate stream longer than its declared size is cut off and rejected
+        let understated = zip("terraform", &content, 8, None, Some(4));
+        assert!(extract_zip_entry(&understated, "terraform").unwrap_err().contains("Checksum mismatch"));
+
+        let huge = zip("terraform", &content, 8, None, Some(u32::MAX));
+        assert!(extract_zip_entry(&huge, "terraform").unwrap_err().contains("too large"));
+    }
+
+    #[test]
+    fn rejects_missing_entries_and_garbage() {
+        let archive = zip("README.md", b"hello", 0, None, None);
+        assert!(extract_zip_entry(&archive, "terraform").unwrap_err().contains("does not contain"));
+        assert!(extract_zip_entry(b"not a zip", "terraform").is_err());
+        assert!(extract_zip_entry(&zip("terraform", b"x", 12, None, None), "terraform")
+            .unwrap_err()
+            .contains("Unsupported zip compression"));
+    }
+
+    #[test]
+    fn archive_names() {
+        assert_eq!(parse_archive_name("terraform_1.9.0_linux_amd64.zip"), Some(("1.9.0", "linux_amd64")));
+        assert_eq!(parse_archive_name("tofu_1.9.0_linux_amd64.zip"), None);
+        assert_eq!(parse_archive_name("terraform_1.9.0_linux_amd64.tar.gz"), None);
+    }
+}
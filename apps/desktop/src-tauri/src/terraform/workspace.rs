@@ -0,0 +1,139 @@
+use serde::Serialize;
+use std::path::Path;
+use tauri::{command, AppHandle, WebviewWindow};
+
+use super::commands::lock_project;
+use super::runner::{self, RunContext};
+
+#[derive(Clone, Serialize)]
+pub struct TerraformWorkspace {
+    pub name: String,
+    /// Whether this is the workspace selected in `.terraform/environment`.
+    pub current: bool,
+}
+
+/// Validate a workspace name. Allows alphanumeric, hyphens and underscores.
+pub fn sanitize_workspace_name(name: &str) -> Result<&str, String> {
+    if name.is_empty() {
+        return Err("Workspace name cannot be empty".to_string());
+    }
+    if name.len() > 90 {
+        return Err(format!("Workspace name is too long: {}", name));
+    }
+    for ch in name.chars() {
+        if !ch.is_ascii_alphanumeric() && ch != '-' && ch != '_' {
+            return Err(format!(
+                "Workspace name contains invalid character '{}': {}",
+                ch, name
+            ));
+        }
+    }
+    if name.starts_with('-') {
+        return Err(format!("Workspace name cannot start with '-': {}", name));
+    }
+    Ok(name)
+}
+
+/// The workspace selected in `terraform_dir`, as recorded in `.terraform/environment`
+/// by `terraform workspace select`. Terraform uses `default` when there is none.
+pub async fn selected_workspace(terraform_dir: &Path) -> String {
+    match tokio::fs::read_to_string(terraform_dir.join(".terraform").join("environment")).await {
+        Ok(name) if !name.trim().is_empty() => name.trim().to_string(),
+        _ => "default".to_string(),
+    }
+}
+
+/// Parse `terraform workspace list` output ("* default\n  dev\n").
+fn parse_workspace_list(output: &str) -> Vec<TerraformWorkspace> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let current = line.trim_start().starts_with('*');
+            let name = line.trim().trim_start_matches('*').trim();
+            if name.is_empty() {
+                None
+            } else {
+                Some(TerraformWorkspace {
+                    name: name.to_string(),
+                    current,
+                })
+            }
+        })
+        .collect()
+}
+
+/// List workspaces for the project's terraform directory.
+#[command]
+pub async fn terraform_workspace_list(project_path: String) -> Result<Vec<TerraformWorkspace>, String> {
+    let ctx = RunContext::for_project(Path::new(&project_path)).await?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    let output = runner::run_terraform_capture(&terraform_dir, &ctx, "workspace", &["list"]).await?;
+    Ok(parse_workspace_list(&output))
+}
+
+/// Get the name of the currently selected workspace.
+#[command]
+pub async fn terraform_workspace_current(project_path: String) -> Result<String, String> {
+    let ctx = RunContext::for_project(Path::new(&project_path)).await?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    let output = runner::run_terraform_capture(&terraform_dir, &ctx, "workspace", &["show"]).await?;
+    Ok(output.trim().to_string())
+}
+
+/// Create a new workspace. Terraform also selects it.
+#[command]
+pub async fn terraform_workspace_new(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    name: String,
+) -> Result<String, String> {
+    sanitize_workspace_name(&name)?;
+    let _lock = lock_project(&app, &window, &project_path, "workspace new")?;
+    let ctx = RunContext::for_project(Path::new(&project_path)).await?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    runner::run_terraform_capture(&terraform_dir, &ctx, "workspace", &["new", "-no-color", &name]).await?;
+    Ok(name)
+}
+
+/// Select an existing workspace.
+#[command]
+pub async fn terraform_workspace_select(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    name: String,
+) -> Result<String, String> {
+    sanitize_workspace_name(&name)?;
+    let _lock = lock_project(&app, &window, &project_path, "workspace select")?;
+    let ctx = RunContext::for_project(Path::new(&project_path)).await?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    runner::run_terraform_capture(&terraform_dir, &ctx, "workspace", &["select", "-no-color", &name]).await?;
+    Ok(name)
+}
+
+/// Delete a workspace. Terraform refuses to delete the current workspace, and
+/// refuses non-empty workspaces unless `force` is set.
+#[command]
+pub async fn terraform_workspace_delete(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    name: String,
+    force: Option<bool>,
+) -> Result<bool, String> {
+    sanitize_workspace_name(&name)?;
+    if name == "default" {
+        return Err("The default workspace cannot be deleted".to_string());
+    }
+    let _lock = lock_project(&app, &window, &project_path, "workspace delete")?;
+    let ctx = RunContext::for_project(Path::new(&project_path)).await?;
+    let terraform_dir = ctx.settings.terraform_dir(Path::new(&project_path));
+    let mut args = vec!["delete", "-no-color"];
+    if force.unwrap_or(false) {
+        args.push("-force");
+    }
+    args.push(&name);
+    runner::run_terraform_capture(&terraform_dir, &ctx, "workspace", &args).await?;
+    Ok(true)
+}
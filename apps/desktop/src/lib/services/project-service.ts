@@ -134,21 +134,13 @@ export async function loadProjectByPath(path: string): Promise<void> {
   ui.closeAllFileTabs();
   project.open(data.path, data.metadata);
 
-  // Merge user secrets into variableValues if this project has a secretsId
+  // Load user secrets beside (never into) variableValues if this project has a secretsId
   if (project.projectConfig.secretsId) {
     try {
-      const secrets = await invoke<Record<string, string>>('load_user_secrets', {
+      project.secretValues = await invoke<Record<string, string>>('load_user_secrets', {
         secretsId: project.projectConfig.secretsId,
       });
-      if (Object.keys(secrets).length > 0) {
-        project.projectConfig = {
-          ...project.projectConfig,
-          variableValues: {
-            ...project.projectConfig.variableValues,
-            ...secrets,
-          },
-        };
-      }
+      project.secretsLoaded = true;
     } catch {
       console.warn('Failed to load user secrets, continuing without them');
     }
@@ -203,18 +195,22 @@ export async function saveDiagram(): Promise<void> {
       .map((v) => v.name),
   );
 
-  // Split variableValues into public (saved in .tstudio) and secret (saved in app data)
+  // Split variableValues into public (saved in .tstudio) and secret (saved in app data).
+  // Sensitive values normally live in project.secretValues already; any left in
+  // variableValues are moved over.
   const allValues = { ...project.projectConfig.variableValues };
   const publicValues: Record<string, unknown> = {};
-  const secretValues: Record<string, string> = {};
+  const secretValues: Record<string, string> = { ...project.secretValues };
 
   for (const [key, value] of Object.entries(allValues)) {
-    if (sensitiveNames.has(key) && value && typeof value === 'string') {
-      secretValues[key] = value;
+    if (sensitiveNames.has(key) && typeof value === 'string') {
+      if (value) secretValues[key] = value;
     } else {
       publicValues[key] = value;
     }
   }
+  project.secretValues = secretValues;
+  project.projectConfig = { ...project.projectConfig, variableValues: publicValues };
 
   // Generate a secretsId if this project doesn't have one yet and has secrets
   if (!project.projectConfig.secretsId && Object.keys(secretValues).length > 0) {
@@ -222,16 +218,10 @@ export async function saveDiagram(): Promise<void> {
     project.projectConfig = { ...project.projectConfig, secretsId };
   }
 
-  // Build config for saving — with sensitive values stripped out
-  const configForSave = {
-    ...project.projectConfig,
-    variableValues: publicValues,
-  };
-
   // Save diagram, project config (without secrets), and cost in parallel
   const savePromises: Promise<unknown>[] = [
     storage.saveDiagram(project.path, diagramData),
-    storage.saveProjectConfig(project.path, configForSave),
+    storage.saveProjectConfig(project.path, project.projectConfig),
     cost.hasPrices
       ? invoke('save_cost', {
           projectPath: project.path,
@@ -240,8 +230,9 @@ export async function saveDiagram(): Promise<void> {
       : Promise.resolve(),
   ];
 
-  // Save secrets to app data dir if we have a secretsId
-  if (project.projectConfig.secretsId) {
+  // Save secrets to app data dir if we have a secretsId. Skipped when the stored
+  // secrets failed to load, so the partial set in memory cannot replace them.
+  if (project.projectConfig.secretsId && project.secretsLoaded) {
     savePromises.push(
       invoke('save_user_secrets', {
         secretsId: project.projectConfig.secretsId,
@@ -255,6 +246,46 @@ export async function saveDiagram(): Promise<void> {
   logger.info(`[project] Project "${project.name}" saved`);
 }
 
+/**
+ * Write the current sensitive variable values to the user secrets store so a
+ * terraform run can read them, and return the project's secretsId.
+ * Returns undefined when the project has no secrets. When the stored secrets
+ * failed to load they are left untouched and the run reads them as they are.
+ */
+export async function syncUserSecrets(): Promise<string | undefined> {
+  if (project.projectConfig.secretsId && !project.secretsLoaded) {
+    return project.projectConfig.secretsId;
+  }
+  if (!project.projectConfig.secretsId) {
+    if (Object.keys(project.secretValues).length === 0) return undefined;
+    const secretsId = await invoke<string>('generate_secrets_id');
+    project.projectConfig = { ...project.projectConfig, secretsId };
+    project.markDirty();
+  }
+  await invoke('save_user_secrets', {
+    secretsId: project.projectConfig.secretsId,
+    secrets: project.secretValues,
+  });
+  return project.projectConfig.secretsId;
+}
+
+/**
+ * Store backend credentials (e.g. `access_key`, `password`) for `terraform init`.
+ * They live in the user secrets store; the project file's backend settings must
+ * not hold them.
+ */
+export async function saveBackendSecrets(secrets: Record<string, string>): Promise<void> {
+  if (!project.projectConfig.secretsId) {
+    const secretsId = await invoke<string>('generate_secrets_id');
+    project.projectConfig = { ...project.projectConfig, secretsId };
+    project.markDirty();
+  }
+  await invoke('save_backend_secrets', {
+    secretsId: project.projectConfig.secretsId,
+    secrets,
+  });
+}
+
 /**
  * Pick a folder using the native dialog.
  */
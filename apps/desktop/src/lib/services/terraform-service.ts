@@ -12,13 +12,16 @@ import { diagram } from '$lib/stores/diagram.svelte';
 import { project } from '$lib/stores/project.svelte';
 import {
   terraform,
+  type EngineReport,
   type TerraformCommand,
+  type TerraformDiagnostic,
   type TerraformJsonResult,
 } from '$lib/stores/terraform.svelte';
 import { ui } from '$lib/stores/ui.svelte';
 import { validation, type ProblemEntry } from '$lib/stores/validation.svelte';
 import { plan, parsePlanChanges, type PlanAction, type PlanResourceChange } from '$lib/stores/plan.svelte';
 import { convertToResourceInstances, extractOutputBindings } from './diagram-converter';
+import { syncUserSecrets } from './project-service';
 
 // Lazy-load notification plugin to avoid errors if not available
 // eslint-disable-next-line @typescript-eslint/no-explicit-any
@@ -130,20 +133,24 @@ function computeDiagramHash(): string {
 }
 
 /**
- * Check if terraform CLI is available.
+ * Check which engines (terraform, OpenTofu) are available, and whether the
+ * project's selected engine is installed.
  */
 export async function checkTerraform(): Promise<void> {
   try {
-    const version = await invoke<string>('check_terraform');
-    terraform.terraformVersion = version;
-    terraform.terraformInstalled = true;
+    const report = await invoke<EngineReport>('check_terraform', { projectPath: project.path ?? null });
+    terraform.engines = report.engines;
+    const selected = report.engines.find((e) => e.kind === report.selected);
+    terraform.terraformInstalled = selected?.installed ?? false;
+    terraform.terraformVersion = selected?.installed ? `${selected.name} v${selected.version}` : null;
   } catch {
     terraform.terraformInstalled = false;
   }
 }
 
 /**
- * Generate HCL from the current diagram and write to the project's terraform/ folder.
+ * Generate HCL from the current diagram and write it to the project's terraform
+ * root (`terraform/` unless `projectConfig.terraform.root` names another directory).
  */
 export async function generateAndWrite(): Promise<Record<string, string>> {
   if (!project.path) throw new Error('No project open');
@@ -266,7 +273,28 @@ export async function generateAndWrite(): Promise<Record<string, string>> {
       }
     }
 
-    await storage.writeTerraformFiles(project.path, fileMap);
+    try {
+      await storage.writeTerraformFiles(project.path, fileMap, result.addresses);
+    } catch (writeErr) {
+      // Syntax errors found while formatting the generated files
+      const diagnostics = (writeErr as any)?.diagnostics as TerraformDiagnostic[] | undefined;
+      if (diagnostics?.length) {
+        const details = diagnostics.map((d) =>
+          d.range
+            ? `${d.range.filename}:${d.range.start.line}:${d.range.start.column}: ${d.detail}`
+            : `${d.summary}: ${d.detail}`,
+        );
+        for (const detail of details) {
+          terraform.appendError(detail);
+        }
+        validation.setTerraformProblems(
+          convertDiagnosticsToProblems({ success: false, code: 1, diagnostics, resource_changes: [] }, 'validate'),
+        );
+        ui.openBottomPanel('problems');
+        (writeErr as any).validationErrors = details;
+      }
+      throw writeErr;
+    }
 
     // Populate generated file list for TerraformSidebar
     ui.generatedFiles = Object.keys(fileMap);
@@ -276,7 +304,7 @@ export async function generateAndWrite(): Promise<Record<string, string>> {
     terraform.markFilesGenerated(diagramHash);
 
     terraform.appendInfo(
-      `Generated ${Object.keys(fileMap).length} files to ${project.path}/terraform`,
+      `Generated ${Object.keys(fileMap).length} files to ${project.path}/${project.projectConfig.terraform?.root ?? 'terraform'}`,
     );
     terraform.setStatus('success');
     logger.info(`[hcl] HCL generation complete — ${Object.keys(fileMap).length} files in ${Math.round(performance.now() - t0)}ms`);
@@ -294,10 +322,23 @@ export async function generateAndWrite(): Promise<Record<string, string>> {
  * Returns list of missing variable names, or empty array if all are satisfied.
  */
 export function validateVariablesBeforeRun(): string[] {
-  const missing = terraform.getMissingVariables(project.projectConfig.variableValues);
+  const missing = terraform.getMissingVariables(project.allVariableValues);
   return missing.map(v => v.name);
 }
 
+/** Variable settings for a terraform run; mirrors `RunOptions` in the backend. */
+interface RunOptions {
+  secretsId?: string;
+}
+
+/**
+ * Options handing the project's sensitive variables to terraform. The backend
+ * reads them from the user secrets store and passes them as TF_VAR_* variables.
+ */
+async function runOptions(): Promise<RunOptions> {
+  return { secretsId: await syncUserSecrets() };
+}
+
 /**
  * Build a lookup map from terraform address to diagram node ID.
  */
@@ -419,27 +460,70 @@ function updateNodeErrorStatus(result: TerraformJsonResult) {
   }
 }
 
+/** Destroy plan result from Rust backend (snake_case) */
+interface DestroyPlanResult {
+  success: boolean;
+  code: number;
+  diagnostics: TerraformDiagnostic[];
+  resources: string[];
+  token: string | null;
+  project_name: string;
+  expires_in_secs: number;
+}
+
+/** Resources listed by name in the destroy confirmation before summarising the rest */
+const DESTROY_LIST_LIMIT = 15;
+
+/**
+ * Plan a destroy, show what it would delete, and apply it only after the user
+ * types the project name back. Returns null when the user cancels.
+ */
+async function planAndConfirmDestroy(projectPath: string): Promise<TerraformJsonResult | null> {
+  const planned = await invoke<DestroyPlanResult>('terraform_destroy_plan', {
+    projectPath,
+    options: await runOptions(),
+  });
+  if (!planned.success || !planned.token) {
+    return { success: false, code: planned.code, diagnostics: planned.diagnostics, resource_changes: [] };
+  }
+  if (planned.resources.length === 0) {
+    terraform.appendInfo('Nothing to destroy.');
+    return { success: true, code: 0, diagnostics: planned.diagnostics, resource_changes: [] };
+  }
+
+  const listed = planned.resources.slice(0, DESTROY_LIST_LIMIT).join('\n');
+  const remaining = planned.resources.length - DESTROY_LIST_LIMIT;
+  const typedName = await ui.confirm({
+    title: 'Destroy Infrastructure',
+    message:
+      `This will permanently destroy ${planned.resources.length} resources. This action cannot be undone.\n\n` +
+      listed +
+      (remaining > 0 ? `\n...and ${remaining} more` : ''),
+    confirmLabel: 'Destroy',
+    cancelLabel: 'Cancel',
+    danger: true,
+    requireText: planned.project_name,
+  });
+  if (typedName === null) return null;
+
+  // Send what the user typed; the backend checks it against the project name
+  return invoke<TerraformJsonResult>('terraform_destroy_apply', {
+    projectPath,
+    token: planned.token,
+    confirmName: typedName,
+  });
+}
+
 /**
  * Run a terraform command, streaming output to the terraform store.
  * For plan/apply/destroy, uses JSON mode for structured error parsing.
+ * Destroy plans first and asks for the project name before deleting anything.
  */
 export async function runTerraformCommand(
   command: TerraformCommand,
 ): Promise<boolean> {
   if (!project.path) throw new Error('No project open');
 
-  // Confirm before destroying infrastructure
-  if (command === 'destroy') {
-    const confirmed = await ui.confirm({
-      title: 'Destroy Infrastructure',
-      message: 'This will permanently destroy all managed infrastructure. This action cannot be undone. Are you sure?',
-      confirmLabel: 'Destroy',
-      cancelLabel: 'Cancel',
-      danger: true,
-    });
-    if (!confirmed) return false;
-  }
-
   // For commands that modify infrastructure, check for stale files
   if (['plan', 'apply', 'destroy'].includes(command) && terraform.filesStale) {
     const proceed = await ui.confirm({
@@ -516,10 +600,20 @@ export async function runTerraformCommand(
   try {
     // Commands with JSON output (validate, plan, apply, destroy)
     if (['validate', 'plan', 'apply', 'destroy'].includes(command)) {
-      const result = await invoke<TerraformJsonResult>(
-        `terraform_${command}`,
-        { projectPath: project.path },
-      );
+      const result = command === 'destroy'
+        ? await planAndConfirmDestroy(project.path)
+        : await invoke<TerraformJsonResult>(
+          `terraform_${command}`,
+          command === 'validate'
+            ? { projectPath: project.path }
+            : { projectPath: project.path, options: await runOptions() },
+        );
+
+      if (!result) {
+        terraform.appendInfo('Destroy cancelled.');
+        terraform.setStatus('idle');
+        return false;
+      }
 
       // Store result and extract error info
       terraform.setLastResult(result);
@@ -609,6 +703,8 @@ interface RawTerraformPlanResult {
     after: Record<string, unknown> | null;
   }>;
   plan_file_path: string;
+  /** ID of the archived copy of this plan, when it was archived */
+  archive_id?: string;
 }
 
 /**
@@ -664,7 +760,7 @@ export async function runTerraformPlan(): Promise<boolean> {
   try {
     const rawResult = await invoke<RawTerraformPlanResult>(
       'terraform_plan_with_out',
-      { projectPath: project.path },
+      { projectPath: project.path, options: await runOptions() },
     );
 
     success = rawResult.success;
@@ -896,11 +992,9 @@ export async function refreshDeploymentStatus(): Promise<void> {
   const deployedNodeIds = new Set<string>();
 
   try {
-    const jsonStr = await invoke<string>('terraform_show', {
+    const state = await invoke<TerraformShowJson>('terraform_show', {
       projectPath: project.path,
     });
-
-    const state: TerraformShowJson = JSON.parse(jsonStr);
     const resources = collectStateResources(state);
 
     for (const resource of resources) {
@@ -25,6 +25,25 @@ class ProjectStore {
     activeProviders: ['azurerm'],
   });
 
+  /**
+   * Values of sensitive variables. Kept in memory and in the user secrets store
+   * (app data dir) only, never in projectConfig, so they are never written under
+   * the project directory. Terraform receives them as TF_VAR_* environment variables.
+   */
+  secretValues = $state<Record<string, string>>({});
+
+  /**
+   * Whether secretValues holds what the user secrets store has for this project.
+   * False while a project with a secretsId has not loaded them, so the store is
+   * never overwritten with an incomplete set.
+   */
+  secretsLoaded = $state(false);
+
+  /** Public and sensitive variable values together, for checking what is set. */
+  get allVariableValues(): Record<string, unknown> {
+    return { ...this.projectConfig.variableValues, ...this.secretValues };
+  }
+
   open(path: string, metadata: ProjectMetadata) {
     this.path = path;
     this.name = metadata.name;
@@ -34,6 +53,8 @@ class ProjectStore {
       ...metadata.projectConfig,
       variableValues: metadata.projectConfig.variableValues ?? {},
     };
+    this.secretValues = {};
+    this.secretsLoaded = !this.projectConfig.secretsId;
     this.isOpen = true;
     this.isDirty = false;
   }
@@ -49,6 +70,8 @@ class ProjectStore {
   close() {
     this.path = null;
     this.name = '';
+    this.secretValues = {};
+    this.secretsLoaded = false;
     this.isOpen = false;
     this.isDirty = false;
   }
@@ -36,6 +36,37 @@ export interface TerraformJsonResult {
   code: number;
   diagnostics: TerraformDiagnostic[];
   resource_changes: ResourceChangeInfo[];
+  /** Set when `terraform_apply_plan` refused a plan whose configuration changed */
+  stale_plan?: StalePlan;
+}
+
+/** Why a saved plan no longer matches the configuration it was created from */
+export interface StalePlan {
+  reasons: string[];
+  changed_files: string[];
+  planned_head: string | null;
+  current_head: string | null;
+}
+
+/** An IaC engine binary probed by `check_terraform` */
+export interface EngineInfo {
+  kind: 'terraform' | 'opentofu';
+  name: string;
+  binary: string;
+  installed: boolean;
+  version: string | null;
+  capabilities: {
+    import_blocks: boolean;
+    removed_blocks: boolean;
+    state_encryption: boolean;
+  };
+  error: string | null;
+}
+
+/** Result of `check_terraform`: every engine, and the one the project uses */
+export interface EngineReport {
+  selected: EngineInfo['kind'];
+  engines: EngineInfo[];
 }
 
 /** Terraform JSON message emitted via events */
@@ -54,6 +85,7 @@ class TerraformStore {
   outputLines = $state<TerraformOutputLine[]>([]);
   terraformVersion = $state<string | null>(null);
   terraformInstalled = $state<boolean | null>(null);
+  engines = $state<EngineInfo[]>([]);
   collectedVariables = $state<TerraformVariable[]>([]);
 
   /** Whether terraform files are out of sync with diagram */
@@ -57,6 +57,14 @@ export interface EditorTab {
   type: 'canvas' | 'file' | 'dep-graph' | 'diff';
 }
 
+export interface ConfirmOptions {
+  title: string;
+  message: string;
+  confirmLabel?: string;
+  cancelLabel?: string;
+  danger?: boolean;
+}
+
 class UiStore {
   // --- Activity Bar + Side Panel ---
   activeView = $state<SidebarView>('explorer');
@@ -463,25 +471,27 @@ class UiStore {
   }
 
   // --- Confirm dialog ---
-  confirmDialog = $state<{
-    title: string;
-    message: string;
-    confirmLabel?: string;
-    cancelLabel?: string;
-    danger?: boolean;
-    resolve: (confirmed: boolean) => void;
-  } | null>(null);
-
-  /** Show a confirm dialog and return a promise that resolves to true/false. */
-  confirm(opts: { title: string; message: string; confirmLabel?: string; cancelLabel?: string; danger?: boolean }): Promise<boolean> {
-    return new Promise((resolve) => {
+  confirmDialog = $state<(ConfirmOptions & {
+    requireText?: string;
+    resolve: (typed: string | null) => void;
+  }) | null>(null);
+
+  /**
+   * Show a confirm dialog and return a promise that resolves to true/false.
+   * With `requireText`, the confirm button stays disabled until that text is typed,
+   * and the promise resolves to the typed text, or null when cancelled.
+   */
+  confirm(opts: ConfirmOptions & { requireText: string }): Promise<string | null>;
+  confirm(opts: ConfirmOptions): Promise<boolean>;
+  confirm(opts: ConfirmOptions & { requireText?: string }): Promise<boolean | string | null> {
+    return new Promise<string | null>((resolve) => {
       this.confirmDialog = { ...opts, resolve };
-    });
+    }).then((typed) => (opts.requireText ? typed : typed !== null));
   }
 
-  /** Called by ConfirmDialog component to close and resolve. */
-  resolveConfirm(confirmed: boolean) {
-    this.confirmDialog?.resolve(confirmed);
+  /** Called by ConfirmDialog component to close and resolve; null cancels. */
+  resolveConfirm(typed: string | null) {
+    this.confirmDialog?.resolve(typed);
     this.confirmDialog = null;
   }
 
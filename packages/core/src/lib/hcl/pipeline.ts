@@ -28,14 +28,43 @@ export interface ProjectConfig {
   variableValues: Record<string, unknown>;
   layoutAlgorithm?: LayoutAlgorithm;
   namingConvention?: NamingConvention;
+  /**
+   * Remote state backend. The desktop app writes a partial backend block and
+   * passes `config` to `terraform init` as -backend-config flags.
+   */
   backend?: {
-    type: string;
+    type: 'azurerm' | 'local' | 'http';
     config: Record<string, string>;
   };
   /** Project-level edge style defaults per category */
   edgeStyles?: ProjectEdgeStyles;
   /** Which cloud providers this project targets. undefined = ['azurerm'] for backward compat. */
   activeProviders?: ProviderId[];
+  /**
+   * HCL blocks kept verbatim when a project was imported from existing .tf files
+   * (unknown resource types, modules, locals, ...). Written to `imported.tf`.
+   */
+  rawHcl?: string[];
+  /**
+   * CLI the desktop app runs for this project. Defaults to terraform on PATH.
+   * `path` must be absolute and name the engine's own binary. `managed` picks
+   * the newest cached terraform binary that satisfies `required_version`.
+   */
+  engine?: {
+    type: 'terraform' | 'opentofu';
+    path?: string;
+    managed?: boolean;
+  };
+  /**
+   * How the desktop app runs terraform. `root` is relative to the project dir
+   * (default `terraform`); `extraArgs` is limited to an allow-list in the backend.
+   */
+  terraform?: {
+    root?: string;
+    parallelism?: number;
+    lockTimeout?: string;
+    extraArgs?: Array<'-compact-warnings' | '-refresh=false' | '-lock=false' | '-upgrade'>;
+  };
   /** Stable ID linking this project to its user secrets store (sensitive variable values). */
   secretsId?: string;
 }
@@ -53,6 +82,11 @@ export interface PipelineInput {
 export interface PipelineResult {
   files: GeneratedFiles;
   collectedVariables: TerraformVariable[];
+  /**
+   * Full terraform address per instanceId, e.g. `module.net.azurerm_subnet.app`.
+   * Used to detect renamed nodes and generate `moved` blocks.
+   */
+  addresses?: Record<string, string>;
   errors?: PipelineValidationError[];
 }
 
@@ -158,9 +192,12 @@ export class HclPipeline {
       }
     }
 
+    const addresses = this.collectAddresses(rootResources, moduleResourceMap, modules, addressMap);
+
     // If no modules with resources, run the original non-module path (unchanged behavior)
     if (moduleResourceMap.size === 0) {
-      return this.generateFlat(input, realResources, resourceMap, addressMap, subscriptionId, subscriptionAliasMap, subscriptionConfigMap);
+      const result = this.generateFlat(input, realResources, resourceMap, addressMap, subscriptionId, subscriptionAliasMap, subscriptionConfigMap);
+      return { ...result, addresses };
     }
 
     // ── Module-aware generation ────────────────────────────────────
@@ -424,7 +461,7 @@ export class HclPipeline {
     const blockBuilder = new HclBlockBuilder();
     const rootFiles = blockBuilder.assemble(
       sortedBlocks,
-      providerBuilder.generateTerraformBlock('>= 1.0', projectConfig.backend),
+      providerBuilder.generateTerraformBlock('>= 1.0'),
       providerBuilder.generateProviderBlocks(),
       variableCollector.generateVariablesHcl(),
       outputCollector.generateOutputsHcl(),
@@ -455,12 +492,43 @@ export class HclPipeline {
     // 13. Generate .gitignore
     files['.gitignore'] = this.generateGitignore();
 
+    // 14. Blocks preserved from an imported project
+    const importedHcl = this.generateImportedHcl(projectConfig, allVars);
+    if (importedHcl) files['imported.tf'] = importedHcl;
+
     return {
       files,
       collectedVariables: allVars,
+      addresses,
     };
   }
 
+  /**
+   * Build the instanceId -> full terraform address map.
+   * Resources in template modules are instantiated once per module instance and
+   * have no single address, so they are left out.
+   */
+  private collectAddresses(
+    rootResources: ResourceInstance[],
+    moduleResourceMap: Map<string, ResourceInstance[]>,
+    modules: ModuleDefinition[],
+    addressMap: Map<string, string>,
+  ): Record<string, string> {
+    const addresses: Record<string, string> = {};
+    for (const resource of rootResources) {
+      const addr = addressMap.get(resource.instanceId);
+      if (addr) addresses[resource.instanceId] = addr;
+    }
+    for (const mod of modules) {
+      if (mod.isTemplate) continue;
+      for (const resource of moduleResourceMap.get(mod.id) ?? []) {
+        const addr = addressMap.get(resource.instanceId);
+        if (addr) addresses[resource.instanceId] = `module.${mod.name}.${addr}`;
+      }
+    }
+    return addresses;
+  }
+
   /**
    * Original flat generation (no modules). Preserved for backward compatibility.
    */
@@ -521,7 +589,7 @@ export class HclPipeline {
     const blockBuilder = new HclBlockBuilder();
     const files = blockBuilder.assemble(
       sortedBlocks,
-      providerBuilder.generateTerraformBlock('>= 1.0', projectConfig.backend),
+      providerBuilder.generateTerraformBlock('>= 1.0'),
       providerBuilder.generateProviderBlocks(),
       variableCollector.generateVariablesHcl(),
       outputCollector.generateOutputsHcl(),
@@ -533,6 +601,8 @@ export class HclPipeline {
     if (tfvars.trim()) files['terraform.tfvars'] = tfvars;
     if (allVars.length > 0) files['terraform.tfvars.example'] = this.generateTfvarsExample(allVars, projectConfig);
     files['.gitignore'] = this.generateGitignore();
+    const importedHcl = this.generateImportedHcl(projectConfig, allVars);
+    if (importedHcl) files['imported.tf'] = importedHcl;
 
     return { files, collectedVariables: allVars };
   }
@@ -888,6 +958,8 @@ export class HclPipeline {
     const lines: string[] = [];
 
     for (const v of variables) {
+      // Sensitive values reach terraform only as TF_VAR_* environment variables
+      if (v.sensitive) continue;
       const value = values[v.name];
       if (value !== undefined && value !== '') {
         lines.push(`${v.name} = ${this.formatTfvarValue(value)}`);
@@ -940,6 +1012,22 @@ export class HclPipeline {
     return lines.join('\n');
   }
 
+  /**
+   * Join the raw blocks kept from an imported project. An imported `variable`
+   * block is dropped when the generator declares a variable of the same name,
+   * since terraform rejects duplicate declarations.
+   */
+  private generateImportedHcl(projectConfig: ProjectConfig, variables: TerraformVariable[]): string {
+    const declared = new Set(variables.map((v) => v.name));
+    return (projectConfig.rawHcl ?? [])
+      .map((block) => block.trim())
+      .filter((block) => {
+        const name = /^variable\s+"([^"]+)"/.exec(block)?.[1];
+        return block !== '' && !(name && declared.has(name));
+      })
+      .join('\n\n');
+  }
+
   /** Generate a .gitignore for the terraform working directory. */
   private generateGitignore(): string {
     return [
@@ -33,10 +33,7 @@ export class ProviderConfigBuilder {
   /**
    * Generates the terraform.tf content with required_version and required_providers.
    */
-  generateTerraformBlock(
-    terraformVersion = '>= 1.0',
-    backend?: { type: string; config: Record<string, string> },
-  ): string {
+  generateTerraformBlock(terraformVersion = '>= 1.0'): string {
     const lines: string[] = ['terraform {'];
     lines.push(`  required_version = "${terraformVersion}"`);
     lines.push('');
@@ -49,15 +46,6 @@ export class ProviderConfigBuilder {
       lines.push('  }');
     }
 
-    if (backend) {
-      lines.push('');
-      lines.push(`  backend "${backend.type}" {`);
-      for (const [key, value] of Object.entries(backend.config)) {
-        lines.push(`    ${key} = "${value}"`);
-      }
-      lines.push('  }');
-    }
-
     lines.push('}');
     return lines.join('\n');
   }
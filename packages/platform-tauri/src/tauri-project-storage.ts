@@ -23,8 +23,21 @@ export class TauriProjectStorage implements IProjectStorage {
     await invoke('save_project_config', { projectPath, projectConfig });
   }
 
-  async writeTerraformFiles(projectPath: string, files: Record<string, string>): Promise<void> {
-    await invoke('write_terraform_files', { projectPath, files });
+  async writeTerraformFiles(
+    projectPath: string,
+    files: Record<string, string>,
+    addresses?: Record<string, string>,
+  ): Promise<void> {
+    const result = await invoke<{ success: boolean; path: string; diagnostics: unknown[] }>(
+      'write_terraform_files',
+      { projectPath, files, addresses },
+    );
+    if (!result.success) {
+      // Syntax errors in the generated HCL; nothing was written
+      const err = new Error('Generated HCL failed syntax check');
+      (err as any).diagnostics = result.diagnostics;
+      throw err;
+    }
   }
 
   async readTerraformFiles(projectPath: string): Promise<Record<string, string>> {
@@ -72,8 +72,15 @@ export interface IProjectStorage {
   /**
    * Write Terraform HCL files to `{projectPath}/terraform/`.
    * The `files` map is `{ filename: content }`, e.g. `{ 'main.tf': '...' }`.
+   * `addresses` maps node instanceIds to terraform addresses; implementations may
+   * compare it with the previous write to generate `moved` blocks for renames.
+   * Implementations may syntax-check the files first and reject the whole write.
    */
-  writeTerraformFiles(projectPath: string, files: Record<string, string>): Promise<void>;
+  writeTerraformFiles(
+    projectPath: string,
+    files: Record<string, string>,
+    addresses?: Record<string, string>,
+  ): Promise<void>;
 
   /**
    * Read all `.tf` files from `{projectPath}/terraform/`.